@@ -0,0 +1,66 @@
+//! Scalar against batch conversions.
+//!
+//! `cargo bench --bench batch` runs the batch kernels on the calling thread and
+//! `cargo bench --bench batch --features parallel` on the rayon pool, the group names telling the two apart.
+
+use criterion::{BenchmarkId, Criterion, Throughput, black_box, criterion_group, criterion_main};
+use landy::prelude::*;
+
+const SIZES: [usize; 3] = [1_000, 100_000, 1_000_000];
+
+const MODE: &str = if cfg!(feature = "parallel") { "parallel" } else { "serial" };
+
+fn points(len: usize) -> Vec<LLA> {
+    (0..len)
+        .map(|i| {
+            let i = i as f64;
+            LLA::from_degs(Degrees(45. + (i * 1e-5) % 1.), Degrees(-73. + (i * 3e-5) % 1.), (i % 500.) as f32)
+        })
+        .collect()
+}
+
+fn lla_to_ecef(c: &mut Criterion) {
+    let mut group = c.benchmark_group(format!("lla_to_ecef/{}", MODE));
+    for len in SIZES {
+        let scalar = points(len);
+        let batch: LLABatch = scalar.iter().copied().collect();
+        group.throughput(Throughput::Elements(len as u64));
+        group.bench_with_input(BenchmarkId::new("scalar", len), &scalar, |b, points| {
+            b.iter(|| points.iter().map(LLA::to_ecef).collect::<Vec<_>>())
+        });
+        group.bench_with_input(BenchmarkId::new("batch", len), &batch, |b, batch| b.iter(|| lla_to_ecef_batch(black_box(batch))));
+    }
+    group.finish();
+}
+
+fn ecef_to_lla(c: &mut Criterion) {
+    let mut group = c.benchmark_group(format!("ecef_to_lla/{}", MODE));
+    for len in SIZES {
+        let scalar: Vec<ECEF> = points(len).iter().map(LLA::to_ecef).collect();
+        let batch: ECEFBatch = scalar.iter().copied().collect();
+        group.throughput(Throughput::Elements(len as u64));
+        group.bench_with_input(BenchmarkId::new("scalar", len), &scalar, |b, points| {
+            b.iter(|| points.iter().map(ECEF::to_lla).collect::<Vec<_>>())
+        });
+        group.bench_with_input(BenchmarkId::new("batch", len), &batch, |b, batch| b.iter(|| ecef_to_lla_batch(black_box(batch))));
+    }
+    group.finish();
+}
+
+fn lla_to_tangent(c: &mut Criterion) {
+    let reference = Reference::new(LLA::from_degs(Degrees(45.5), Degrees(-72.5), 0.));
+    let mut group = c.benchmark_group(format!("lla_to_tangent/{}", MODE));
+    for len in SIZES {
+        let scalar = points(len);
+        let batch: LLABatch = scalar.iter().copied().collect();
+        group.throughput(Throughput::Elements(len as u64));
+        group.bench_with_input(BenchmarkId::new("scalar", len), &scalar, |b, points| {
+            b.iter(|| points.iter().map(|&lla| reference.lla_to_tangent(lla)).collect::<Vec<_>>())
+        });
+        group.bench_with_input(BenchmarkId::new("batch", len), &batch, |b, batch| b.iter(|| reference.lla_to_tangent_batch(black_box(batch))));
+    }
+    group.finish();
+}
+
+criterion_group!(benches, lla_to_ecef, ecef_to_lla, lla_to_tangent);
+criterion_main!(benches);
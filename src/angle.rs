@@ -0,0 +1,174 @@
+use core::f64::consts::{PI, TAU};
+use core::fmt;
+use core::ops::{Add, Div, Mul, Neg, Sub};
+
+/// Declares an angle newtype over `f64` with the arithmetic that keeps its unit.
+macro_rules! angle {
+    ($(#[$meta:meta])* $name:ident) => {
+        $(#[$meta])*
+        #[derive(Clone, Copy, Debug, PartialEq, PartialOrd, Default)]
+        #[repr(transparent)]
+        pub struct $name(pub f64);
+
+        impl Add for $name {
+            type Output = Self;
+            fn add(self, rhs: Self) -> Self::Output {
+                Self(self.0 + rhs.0)
+            }
+        }
+
+        impl Sub for $name {
+            type Output = Self;
+            fn sub(self, rhs: Self) -> Self::Output {
+                Self(self.0 - rhs.0)
+            }
+        }
+
+        impl Mul<f64> for $name {
+            type Output = Self;
+            fn mul(self, rhs: f64) -> Self::Output {
+                Self(self.0 * rhs)
+            }
+        }
+
+        impl Div<f64> for $name {
+            type Output = Self;
+            fn div(self, rhs: f64) -> Self::Output {
+                Self(self.0 / rhs)
+            }
+        }
+
+        impl Neg for $name {
+            type Output = Self;
+            fn neg(self) -> Self::Output {
+                Self(-self.0)
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                fmt::Display::fmt(&self.0, f)
+            }
+        }
+    };
+}
+
+angle!(
+    /// Angle in radians
+    Radians
+);
+
+angle!(
+    /// Angle in degrees
+    Degrees
+);
+
+/// Wraps `value` into `[0, turn)`. `rem_euclid` rounds tiny negative values up to `turn` itself.
+fn wrap_unsigned(value: f64, turn: f64) -> f64 {
+    let wrapped = value.rem_euclid(turn);
+    if wrapped < turn { wrapped } else { 0. }
+}
+
+/// Wraps `value` into `[-half_turn, half_turn)`
+fn wrap_signed(value: f64, half_turn: f64) -> f64 {
+    wrap_unsigned(value + half_turn, 2. * half_turn) - half_turn
+}
+
+impl Radians {
+    pub fn to_degrees(self) -> Degrees {
+        Degrees(self.0.to_degrees())
+    }
+
+    /// Wraps into `[-π, π)`
+    pub fn wrap_pi(self) -> Self {
+        Self(wrap_signed(self.0, PI))
+    }
+
+    /// Wraps into `[0, 2π)`
+    pub fn wrap_two_pi(self) -> Self {
+        Self(wrap_unsigned(self.0, TAU))
+    }
+
+    pub fn sin(self) -> f64 {
+        self.0.sin()
+    }
+
+    pub fn cos(self) -> f64 {
+        self.0.cos()
+    }
+
+    pub fn tan(self) -> f64 {
+        self.0.tan()
+    }
+}
+
+impl Degrees {
+    pub fn to_radians(self) -> Radians {
+        Radians(self.0.to_radians())
+    }
+
+    /// Wraps into `[-180, 180)`
+    pub fn wrap_180(self) -> Self {
+        Self(wrap_signed(self.0, 180.))
+    }
+
+    /// Wraps into `[0, 360)`
+    pub fn wrap_360(self) -> Self {
+        Self(wrap_unsigned(self.0, 360.))
+    }
+}
+
+impl From<Degrees> for Radians {
+    fn from(value: Degrees) -> Self {
+        value.to_radians()
+    }
+}
+
+impl From<Radians> for Degrees {
+    fn from(value: Radians) -> Self {
+        value.to_degrees()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wrap_edges() {
+        assert_eq!(Radians(PI).wrap_pi(), Radians(-PI));
+        assert_eq!(Radians(-PI).wrap_pi(), Radians(-PI));
+        assert_eq!(Radians(TAU).wrap_two_pi(), Radians(0.));
+        assert_eq!(Radians(-TAU).wrap_two_pi(), Radians(0.));
+        assert_eq!(Degrees(180.).wrap_180(), Degrees(-180.));
+        assert_eq!(Degrees(-180.).wrap_180(), Degrees(-180.));
+        assert_eq!(Degrees(360.).wrap_360(), Degrees(0.));
+        assert_eq!(Degrees(-360.).wrap_360(), Degrees(0.));
+        //Tiny negative values stay inside the half-open range
+        assert_eq!(Radians(-1e-20).wrap_two_pi(), Radians(0.));
+        assert_eq!(Degrees(-1e-20).wrap_360(), Degrees(0.));
+        assert!(Degrees(180. - 1e-14).wrap_180().0 < 180.);
+    }
+
+    #[test]
+    fn wraps_negative_and_large_angles() {
+        assert!((Degrees(-90.).wrap_360().0 - 270.).abs() < 1e-12);
+        assert!((Degrees(-190.).wrap_180().0 - 170.).abs() < 1e-12);
+        assert!((Degrees(725.).wrap_180().0 - 5.).abs() < 1e-12);
+        assert!((Degrees(-725.).wrap_360().0 - 355.).abs() < 1e-12);
+        assert!((Radians(-PI / 2.).wrap_two_pi().0 - 1.5 * PI).abs() < 1e-12);
+        assert!((Radians(3. * PI + 0.5).wrap_pi().0 - (-PI + 0.5)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn arithmetic_keeps_the_unit() {
+        assert_eq!(Degrees(10.) + Degrees(5.), Degrees(15.));
+        assert_eq!(Degrees(10.) - Degrees(15.), Degrees(-5.));
+        assert_eq!(Radians(1.5) * 2., Radians(3.));
+        assert_eq!(Radians(3.) / 2., Radians(1.5));
+        assert_eq!(-Degrees(30.), Degrees(-30.));
+        assert!((Radians::from(Degrees(180.)).0 - PI).abs() < 1e-15);
+        assert!((Degrees::from(Radians(PI / 2.)).0 - 90.).abs() < 1e-12);
+        assert_eq!(Degrees(12.5).to_string(), "12.5");
+    }
+}
@@ -0,0 +1,149 @@
+use nalgebra::{Rotation3, UnitQuaternion};
+
+use crate::prelude::*;
+
+/// Orientation of a body frame (forward-right-down) relative to the local NED frame
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Attitude {
+    /// body to NED
+    quaternion: UnitQuaternion<f64>,
+}
+
+impl Default for Attitude {
+    fn default() -> Self {
+        Self {
+            quaternion: UnitQuaternion::identity(),
+        }
+    }
+}
+
+impl Attitude {
+    /// Euler ZYX: yaw about down, then pitch about the new right axis, then roll about forward
+    pub fn from_rpy(roll: impl Into<Radians>, pitch: impl Into<Radians>, yaw: impl Into<Radians>) -> Self {
+        Self {
+            quaternion: UnitQuaternion::from_euler_angles(roll.into().0, pitch.into().0, yaw.into().0),
+        }
+    }
+
+    pub fn from_quaternion(quaternion: UnitQuaternion<f64>) -> Self {
+        Self { quaternion }
+    }
+
+    /// Builds from a body to NED direction cosine matrix, re-orthonormalising it if needed
+    pub fn from_dcm(dcm: Matrix3<f64>) -> Self {
+        Self {
+            quaternion: UnitQuaternion::from_rotation_matrix(&Rotation3::from_matrix(&dcm)),
+        }
+    }
+
+    /// (roll, pitch, yaw)
+    pub fn rpy(&self) -> (Radians, Radians, Radians) {
+        let (roll, pitch, yaw) = self.quaternion.euler_angles();
+        (Radians(roll), Radians(pitch), Radians(yaw))
+    }
+
+    pub fn quaternion(&self) -> UnitQuaternion<f64> {
+        self.quaternion
+    }
+
+    /// Body to NED direction cosine matrix
+    pub fn dcm(&self) -> Matrix3<f64> {
+        *self.quaternion.to_rotation_matrix().matrix()
+    }
+
+    pub fn body_to_ned(&self, body: Body<f32>) -> Ned<f32> {
+        let v = self.quaternion * body.as_vector().cast::<f64>();
+        Ned::new(v.x as f32, v.y as f32, v.z as f32)
+    }
+
+    pub fn ned_to_body(&self, ned: Ned<f32>) -> Body<f32> {
+        let v = self.quaternion.inverse() * ned.as_vector().cast::<f64>();
+        Body::new(v.x as f32, v.y as f32, v.z as f32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn degrees(roll: f64, pitch: f64, yaw: f64) -> Attitude {
+        Attitude::from_rpy(Degrees(roll), Degrees(pitch), Degrees(yaw))
+    }
+
+    fn assert_vector(actual: &Vector3<f32>, expected: [f32; 3]) {
+        assert!((actual - Vector3::from(expected)).norm() < 1e-5, "{:?} instead of {:?}", actual, expected);
+    }
+
+    #[test]
+    fn yaw_90_points_forward_east() {
+        let attitude = degrees(0., 0., 90.);
+        assert_vector(attitude.body_to_ned(Body::new(1., 0., 0.)).as_vector(), [0., 1., 0.]);
+        //Right wing points south
+        assert_vector(attitude.body_to_ned(Body::new(0., 1., 0.)).as_vector(), [-1., 0., 0.]);
+        assert_vector(attitude.body_to_ned(Body::new(0., 0., 1.)).as_vector(), [0., 0., 1.]);
+    }
+
+    #[test]
+    fn zyx_convention() {
+        //Nose up: forward climbs, pitch about the right axis
+        assert_vector(degrees(0., 30., 0.).body_to_ned(Body::new(1., 0., 0.)).as_vector(), [0.8660254, 0., -0.5]);
+        //Right wing down: roll about forward
+        assert_vector(degrees(30., 0., 0.).body_to_ned(Body::new(0., 1., 0.)).as_vector(), [0., 0.8660254, 0.5]);
+        //Yaw is applied first, then pitch about the new right axis: forward heads east and climbs
+        assert_vector(degrees(0., 30., 90.).body_to_ned(Body::new(1., 0., 0.)).as_vector(), [0., 0.8660254, -0.5]);
+        //The DCM is Rz(yaw) Ry(pitch) Rx(roll)
+        let (roll, pitch, yaw) = (0.3_f64, -0.2_f64, 2.1_f64);
+        let rx = Matrix3::new(1., 0., 0., 0., roll.cos(), -roll.sin(), 0., roll.sin(), roll.cos());
+        let ry = Matrix3::new(pitch.cos(), 0., pitch.sin(), 0., 1., 0., -pitch.sin(), 0., pitch.cos());
+        let rz = Matrix3::new(yaw.cos(), -yaw.sin(), 0., yaw.sin(), yaw.cos(), 0., 0., 0., 1.);
+        assert!((Attitude::from_rpy(Radians(roll), Radians(pitch), Radians(yaw)).dcm() - rz * ry * rx).norm() < 1e-12);
+    }
+
+    #[test]
+    fn representations_round_trip() {
+        for (roll, pitch, yaw) in [(10., 20., 30.), (-170., 45., -100.), (5., -80., 179.), (0., 0., 0.)] {
+            let attitude = degrees(roll, pitch, yaw);
+            let (r, p, y) = attitude.rpy();
+            for (actual, expected) in [(r, roll), (p, pitch), (y, yaw)] {
+                assert!((actual.to_degrees().0 - expected).abs() < 1e-9, "{:?} from {:?}", attitude.rpy(), (roll, pitch, yaw));
+            }
+            let from_dcm = Attitude::from_dcm(attitude.dcm());
+            assert!(from_dcm.quaternion().angle_to(&attitude.quaternion()) < 1e-9);
+            assert_eq!(Attitude::from_quaternion(attitude.quaternion()), attitude);
+            let dcm = attitude.dcm();
+            assert!((dcm * dcm.transpose() - Matrix3::identity()).norm() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn gimbal_lock() {
+        //Roll and yaw are not separable at ±90° pitch, the orientation must survive anyway
+        for pitch in [90., -90., 89.9999, -89.9999] {
+            let attitude = degrees(20., pitch, 60.);
+            let (roll, pitch_back, yaw) = attitude.rpy();
+            assert!(roll.0.is_finite() && yaw.0.is_finite());
+            assert!((pitch_back.to_degrees().0 - pitch).abs() < 1e-6, "{:?}", attitude.rpy());
+            let rebuilt = Attitude::from_rpy(roll, pitch_back, yaw);
+            assert!((rebuilt.dcm() - attitude.dcm()).norm() < 1e-6, "{:?}", attitude.rpy());
+        }
+    }
+
+    #[test]
+    fn body_and_ned_are_inverses() {
+        let attitude = degrees(12., -34., 156.);
+        let body = Body::new(3., -4., 12.);
+        let ned = attitude.body_to_ned(body);
+        assert!((ned.as_vector().norm() - 13.).abs() < 1e-5);
+        assert_vector(attitude.ned_to_body(ned).as_vector(), [3., -4., 12.]);
+    }
+
+    #[test]
+    fn body_offset_to_lla() {
+        let origin = LLA::from_degs(Degrees(45.), Degrees(-73.), 100.);
+        let reference = Reference::new(origin);
+        //100 m ahead of a vehicle heading east and 10 m below it
+        let target = reference.body_offset_to_lla(origin, &degrees(0., 0., 90.), Body::new(100., 0., 10.));
+        let offset = reference.lla_to_tangent(target);
+        assert!((offset - Ned::new(0., 100., 10.)).as_vector().norm() < 1e-3, "{:?}", offset);
+    }
+}
@@ -0,0 +1,338 @@
+//! Batch coordinate conversions over structure-of-arrays buffers.
+//!
+//! Each component lives in its own contiguous `Vec`, so the conversion loops are plain element-wise
+//! arithmetic over slices that the compiler can auto-vectorise. With the `parallel` feature, large
+//! batches are split recursively and converted on the rayon thread pool.
+
+use crate::prelude::*;
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq)]
+pub enum BatchError {
+    #[error("Batch components differ in length: {0:?}")]
+    LengthMismatch(Vec<usize>),
+}
+
+/// Below this many points a batch is converted on the calling thread
+#[cfg(feature = "parallel")]
+const PARALLEL_THRESHOLD: usize = 16 * 1024;
+
+/// Borrowed view of a batch that can be split for parallel processing
+trait Split: Sized + Send {
+    fn len(&self) -> usize;
+    #[cfg_attr(not(feature = "parallel"), allow(dead_code))]
+    fn split_at(self, mid: usize) -> (Self, Self);
+}
+
+/// Declares an owned structure-of-arrays batch along with its shared and mutable slice views
+macro_rules! soa {
+    ($(#[$meta:meta])* $batch:ident, $view:ident, $view_mut:ident, $item:ty { $($field:ident: $t:ty),+ }) => {
+        $(#[$meta])*
+        #[derive(Clone, Debug, Default, PartialEq)]
+        pub struct $batch {
+            $($field: Vec<$t>,)+
+        }
+
+        struct $view<'a> {
+            $($field: &'a [$t],)+
+        }
+
+        struct $view_mut<'a> {
+            $($field: &'a mut [$t],)+
+        }
+
+        impl $batch {
+            /// Batch from its components, which must all have the same length
+            pub fn new($($field: Vec<$t>),+) -> Result<Self, BatchError> {
+                let lengths = [$($field.len()),+];
+                if lengths.iter().any(|&l| l != lengths[0]) {
+                    return Err(BatchError::LengthMismatch(lengths.to_vec()));
+                }
+                Ok(Self { $($field,)+ })
+            }
+
+            $(pub fn $field(&self) -> &[$t] {
+                &self.$field
+            })+
+
+            pub fn with_capacity(capacity: usize) -> Self {
+                Self {
+                    $($field: Vec::with_capacity(capacity),)+
+                }
+            }
+
+            /// `len` zeroed points, to be filled by a conversion
+            fn zeroed(len: usize) -> Self {
+                Self {
+                    $($field: vec![Default::default(); len],)+
+                }
+            }
+
+            pub fn len(&self) -> usize {
+                soa!(@first $(self.$field.len()),+)
+            }
+
+            pub fn is_empty(&self) -> bool {
+                self.len() == 0
+            }
+
+            fn view(&self) -> $view<'_> {
+                $view {
+                    $($field: &self.$field,)+
+                }
+            }
+
+            fn view_mut(&mut self) -> $view_mut<'_> {
+                $view_mut {
+                    $($field: &mut self.$field,)+
+                }
+            }
+        }
+
+        impl Split for $view<'_> {
+            fn len(&self) -> usize {
+                soa!(@first $(self.$field.len()),+)
+            }
+
+            fn split_at(self, mid: usize) -> (Self, Self) {
+                $(let $field = self.$field.split_at(mid);)+
+                ($view { $($field: $field.0,)+ }, $view { $($field: $field.1,)+ })
+            }
+        }
+
+        impl Split for $view_mut<'_> {
+            fn len(&self) -> usize {
+                soa!(@first $(self.$field.len()),+)
+            }
+
+            fn split_at(self, mid: usize) -> (Self, Self) {
+                $(let $field = self.$field.split_at_mut(mid);)+
+                ($view_mut { $($field: $field.0,)+ }, $view_mut { $($field: $field.1,)+ })
+            }
+        }
+
+        impl FromIterator<$item> for $batch {
+            fn from_iter<I: IntoIterator<Item = $item>>(iter: I) -> Self {
+                let mut batch = Self::default();
+                batch.extend(iter);
+                batch
+            }
+        }
+    };
+    (@first $first:expr $(, $rest:expr)*) => { $first };
+}
+
+soa!(
+    /// Geodetic positions, radians and meters above the WGS-84 ellipsoid
+    LLABatch, LLAView, LLAViewMut, LLA { latitude: f64, longitude: f64, altitude: f32 }
+);
+
+soa!(
+    /// ECEF positions (meters)
+    ECEFBatch, ECEFView, ECEFViewMut, ECEF { x: f64, y: f64, z: f64 }
+);
+
+soa!(
+    /// NED tangent vectors of a `Reference` (meters)
+    NedBatch, NedView, NedViewMut, Ned<f32> { north: f32, east: f32, down: f32 }
+);
+
+impl LLABatch {
+    pub fn push(&mut self, lla: LLA) {
+        self.latitude.push(lla.lat().0);
+        self.longitude.push(lla.lon().0);
+        self.altitude.push(lla.altitude);
+    }
+
+    pub fn get(&self, index: usize) -> LLA {
+        LLA::from_rads(Radians(self.latitude[index]), Radians(self.longitude[index]), self.altitude[index])
+    }
+}
+
+impl ECEFBatch {
+    pub fn push(&mut self, ecef: ECEF) {
+        self.x.push(ecef.x);
+        self.y.push(ecef.y);
+        self.z.push(ecef.z);
+    }
+
+    pub fn get(&self, index: usize) -> ECEF {
+        ECEF::new(self.x[index], self.y[index], self.z[index])
+    }
+}
+
+impl NedBatch {
+    pub fn push(&mut self, ned: Ned<f32>) {
+        self.north.push(ned.north());
+        self.east.push(ned.east());
+        self.down.push(ned.down());
+    }
+
+    pub fn get(&self, index: usize) -> Ned<f32> {
+        Ned::new(self.north[index], self.east[index], self.down[index])
+    }
+}
+
+impl Extend<LLA> for LLABatch {
+    fn extend<I: IntoIterator<Item = LLA>>(&mut self, iter: I) {
+        iter.into_iter().for_each(|lla| self.push(lla));
+    }
+}
+
+impl Extend<ECEF> for ECEFBatch {
+    fn extend<I: IntoIterator<Item = ECEF>>(&mut self, iter: I) {
+        iter.into_iter().for_each(|ecef| self.push(ecef));
+    }
+}
+
+impl Extend<Ned<f32>> for NedBatch {
+    fn extend<I: IntoIterator<Item = Ned<f32>>>(&mut self, iter: I) {
+        iter.into_iter().for_each(|ned| self.push(ned));
+    }
+}
+
+/// Runs `kernel` over the whole batch, split across the rayon pool when the `parallel` feature is enabled
+fn dispatch<I: Split, O: Split>(input: I, output: O, kernel: &(impl Fn(I, O) + Sync)) {
+    #[cfg(feature = "parallel")]
+    if input.len() > PARALLEL_THRESHOLD {
+        let mid = input.len() / 2;
+        let (input_left, input_right) = input.split_at(mid);
+        let (output_left, output_right) = output.split_at(mid);
+        rayon::join(|| dispatch(input_left, output_left, kernel), || dispatch(input_right, output_right, kernel));
+        return;
+    }
+    debug_assert_eq!(input.len(), output.len());
+    kernel(input, output)
+}
+
+pub fn lla_to_ecef_batch(lla: &LLABatch) -> ECEFBatch {
+    let mut ecef = ECEFBatch::zeroed(lla.len());
+    dispatch(lla.view(), ecef.view_mut(), &|input: LLAView, output: ECEFViewMut| {
+        let points = input.latitude.iter().zip(input.longitude).zip(input.altitude);
+        let outputs = output.x.iter_mut().zip(output.y.iter_mut()).zip(output.z.iter_mut());
+        for (((&latitude, &longitude), &altitude), ((x, y), z)) in points.zip(outputs) {
+            let (sin_lat, cos_lat) = latitude.sin_cos();
+            let (sin_lon, cos_lon) = longitude.sin_cos();
+            let n = Earth::prime_vertical_radius(Radians(latitude));
+            let altitude = altitude as f64;
+            *x = (n + altitude) * cos_lat * cos_lon;
+            *y = (n + altitude) * cos_lat * sin_lon;
+            *z = (n * (1.0 - Earth::ECCENTRICITY_SQUARED) + altitude) * sin_lat;
+        }
+    });
+    ecef
+}
+
+pub fn ecef_to_lla_batch(ecef: &ECEFBatch) -> LLABatch {
+    let mut lla = LLABatch::zeroed(ecef.len());
+    dispatch(ecef.view(), lla.view_mut(), &|input: ECEFView, output: LLAViewMut| {
+        let points = input.x.iter().zip(input.y).zip(input.z);
+        let outputs = output.latitude.iter_mut().zip(output.longitude.iter_mut()).zip(output.altitude.iter_mut());
+        for (((&x, &y), &z), ((latitude, longitude), altitude)) in points.zip(outputs) {
+            let point = ECEF::new(x, y, z).to_lla();
+            *latitude = point.lat().0;
+            *longitude = point.lon().0;
+            *altitude = point.altitude;
+        }
+    });
+    lla
+}
+
+impl Reference {
+    pub fn ecef_to_tangent_batch(&self, ecef: &ECEFBatch) -> NedBatch {
+        let r = self.ecef_to_ned_rotation();
+        let origin = self.ecef;
+        let mut ned = NedBatch::zeroed(ecef.len());
+        dispatch(ecef.view(), ned.view_mut(), &|input: ECEFView, output: NedViewMut| {
+            //Re-slicing to a common length lets the compiler drop the bounds checks and vectorise
+            let len = input.x.len();
+            let (x, y, z) = (&input.x[..len], &input.y[..len], &input.z[..len]);
+            let (north, east, down) = (&mut output.north[..len], &mut output.east[..len], &mut output.down[..len]);
+            for i in 0..len {
+                let (dx, dy, dz) = (x[i] - origin.x, y[i] - origin.y, z[i] - origin.z);
+                north[i] = (r.m11 * dx + r.m12 * dy + r.m13 * dz) as f32;
+                east[i] = (r.m21 * dx + r.m22 * dy + r.m23 * dz) as f32;
+                down[i] = (r.m31 * dx + r.m32 * dy + r.m33 * dz) as f32;
+            }
+        });
+        ned
+    }
+
+    /// Absolute positions, like `Reference::tangent_to_ecef_position`
+    pub fn tangent_to_ecef_position_batch(&self, ned: &NedBatch) -> ECEFBatch {
+        let r = self.ned_to_ecef_rotation();
+        let origin = self.ecef;
+        let mut ecef = ECEFBatch::zeroed(ned.len());
+        dispatch(ned.view(), ecef.view_mut(), &|input: NedView, output: ECEFViewMut| {
+            let len = input.north.len();
+            let (north, east, down) = (&input.north[..len], &input.east[..len], &input.down[..len]);
+            let (x, y, z) = (&mut output.x[..len], &mut output.y[..len], &mut output.z[..len]);
+            for i in 0..len {
+                let (n, e, d) = (north[i] as f64, east[i] as f64, down[i] as f64);
+                x[i] = origin.x + r.m11 * n + r.m12 * e + r.m13 * d;
+                y[i] = origin.y + r.m21 * n + r.m22 * e + r.m23 * d;
+                z[i] = origin.z + r.m31 * n + r.m32 * e + r.m33 * d;
+            }
+        });
+        ecef
+    }
+
+    pub fn lla_to_tangent_batch(&self, lla: &LLABatch) -> NedBatch {
+        self.ecef_to_tangent_batch(&lla_to_ecef_batch(lla))
+    }
+
+    pub fn tangent_to_lla_batch(&self, ned: &NedBatch) -> LLABatch {
+        ecef_to_lla_batch(&self.tangent_to_ecef_position_batch(ned))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Enough points to be split across threads with the `parallel` feature
+    fn points() -> LLABatch {
+        (0..40_000)
+            .map(|i| {
+                let i = i as f64;
+                LLA::from_degs(Degrees((i * 0.0137) % 180. - 90.), Degrees((i * 0.0291) % 360. - 180.), (i % 5000.) as f32)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn rejects_components_of_different_lengths() {
+        assert_eq!(ECEFBatch::new(vec![0.; 3], vec![0.; 2], vec![0.; 3]), Err(BatchError::LengthMismatch(vec![3, 2, 3])));
+        let batch = NedBatch::new(vec![1., 2.], vec![3., 4.], vec![5., 6.]).unwrap();
+        assert_eq!(batch.len(), 2);
+        assert_eq!(batch.get(1), Ned::new(2., 4., 6.));
+        assert_eq!(batch.east(), [3., 4.]);
+    }
+
+    #[test]
+    fn matches_scalar_conversions() {
+        let lla = points();
+        let ecef = lla_to_ecef_batch(&lla);
+        let back = ecef_to_lla_batch(&ecef);
+        assert_eq!(ecef.len(), lla.len());
+        for index in (0..lla.len()).step_by(97) {
+            let expected = lla.get(index).to_ecef();
+            let point = ecef.get(index);
+            assert_eq!((point.x, point.y, point.z), (expected.x, expected.y, expected.z));
+            let (back, expected) = (back.get(index), point.to_lla());
+            assert_eq!((back.lat(), back.lon(), back.altitude), (expected.lat(), expected.lon(), expected.altitude));
+        }
+    }
+
+    #[test]
+    fn tangent_round_trip() {
+        let reference = Reference::new(LLA::from_degs(Degrees(45.5), Degrees(-73.6), 30.));
+        let ned: NedBatch = (0..40_000).map(|i| Ned::new((i % 200) as f32 * 10. - 1000., (i / 200) as f32 * 10. - 1000., (i % 7) as f32)).collect();
+        let back = reference.lla_to_tangent_batch(&reference.tangent_to_lla_batch(&ned));
+        let ecef = reference.tangent_to_ecef_position_batch(&ned);
+        for index in (0..ned.len()).step_by(97) {
+            assert!((back.get(index) - ned.get(index)).as_vector().norm() < 0.01, "{:?} instead of {:?}", back.get(index), ned.get(index));
+            let expected = reference.tangent_to_ecef_position(ned.get(index));
+            assert!((ecef.get(index) - expected).x.abs() < 1e-6 && (ecef.get(index) - expected).y.abs() < 1e-6 && (ecef.get(index) - expected).z.abs() < 1e-6);
+        }
+    }
+}
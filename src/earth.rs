@@ -1,3 +1,5 @@
+use crate::prelude::*;
+
 pub struct Earth {}
 
 impl Earth {
@@ -7,5 +9,144 @@ impl Earth {
     pub const GEOMAGNETIC_RADIUS: f64 = 6371200.0;
     pub const ANGULAR_SPEED: f64 = 7.2921E-5;
     pub const ANGULAR_SPEED_SQUARED: f64 = Earth::ANGULAR_SPEED * Earth::ANGULAR_SPEED;
-    pub const GRAVITY: f32 = 9.80639076;
+    pub const GRAVITY: f32 = 9.806_391;
+    /// Geocentric gravitational constant GM (m³/s²)
+    pub const GRAVITATIONAL_PARAMETER: f64 = 3.986004418E14;
+    /// Normal gravity at the equator (m/s²)
+    pub const EQUATORIAL_GRAVITY: f64 = 9.7803253359;
+    /// Normal gravity at the poles (m/s²)
+    pub const POLAR_GRAVITY: f64 = 9.8321849378;
+
+    /// Earth rotation angle (IERS 2010), UT1 approximated by UTC. `utc_secs` is seconds since the Unix epoch,
+    /// as reported by the SKYMATE GNSS clock.
+    pub fn rotation_angle(utc_secs: f64) -> Radians {
+        const UNIX_EPOCH_JULIAN_DAY: f64 = 2440587.5;
+        const J2000_JULIAN_DAY: f64 = 2451545.0;
+        let days_since_j2000 = utc_secs / 86400. + (UNIX_EPOCH_JULIAN_DAY - J2000_JULIAN_DAY);
+        //Split the integer days out to keep precision in the fractional turn
+        let turns = 0.7790572732640 + days_since_j2000.fract() + 0.00273781191135448 * days_since_j2000;
+        Radians(turns.fract() * core::f64::consts::TAU).wrap_two_pi()
+    }
+
+    pub fn angular_velocity() -> EcefVec {
+        EcefVec::new(0., 0., Earth::ANGULAR_SPEED)
+    }
+
+    /// Apparent Coriolis acceleration `-2 ω × v` of a body moving at `velocity` relative to the Earth
+    pub fn coriolis_acceleration(velocity: EcefVec) -> EcefVec {
+        let a = -2. * Earth::angular_velocity().as_vector().cross(velocity.as_vector());
+        EcefVec::new(a.x, a.y, a.z)
+    }
+
+    /// Centripetal acceleration `ω × (ω × r)` of a point fixed to the Earth, towards the rotation axis.
+    /// The apparent centrifugal acceleration in ECEF is its opposite.
+    pub fn centripetal_acceleration(position: ECEF) -> EcefVec {
+        EcefVec::new(-Earth::ANGULAR_SPEED_SQUARED * position.x, -Earth::ANGULAR_SPEED_SQUARED * position.y, 0.)
+    }
+
+    /// Normal gravity magnitude (m/s²) on the WGS-84 ellipsoid: Somigliana's closed formula with the
+    /// second-order free-air correction for `height` above the ellipsoid (NIMA TR8350.2, eq. 4-1 and 4-3)
+    pub fn normal_gravity(latitude: impl Into<Radians>, height: f64) -> f64 {
+        let a = Earth::EQUATORIAL_RADIUS;
+        let b = a * (1. - Earth::FLATNESS);
+        let sin_lat_squared = latitude.into().sin().powi(2);
+        let k = b * Earth::POLAR_GRAVITY / (a * Earth::EQUATORIAL_GRAVITY) - 1.;
+        let surface = Earth::EQUATORIAL_GRAVITY * (1. + k * sin_lat_squared) / (1. - Earth::ECCENTRICITY_SQUARED * sin_lat_squared).sqrt();
+        let m = Earth::ANGULAR_SPEED_SQUARED * a * a * b / Earth::GRAVITATIONAL_PARAMETER;
+        surface * (1. - 2. / a * (1. + Earth::FLATNESS + m - 2. * Earth::FLATNESS * sin_lat_squared) * height + 3. / (a * a) * height * height)
+    }
+
+    /// Meridian radius of curvature M (meters)
+    pub fn meridian_radius(latitude: impl Into<Radians>) -> f64 {
+        let w_squared = 1. - Earth::ECCENTRICITY_SQUARED * latitude.into().sin().powi(2);
+        Earth::EQUATORIAL_RADIUS * (1. - Earth::ECCENTRICITY_SQUARED) / (w_squared * w_squared.sqrt())
+    }
+
+    /// Prime-vertical radius of curvature N (meters)
+    pub fn prime_vertical_radius(latitude: impl Into<Radians>) -> f64 {
+        Earth::EQUATORIAL_RADIUS / (1. - Earth::ECCENTRICITY_SQUARED * latitude.into().sin().powi(2)).sqrt()
+    }
+
+    /// Gaussian mean radius √(MN) (meters)
+    pub fn gaussian_radius(latitude: impl Into<Radians>) -> f64 {
+        let latitude = latitude.into();
+        (Earth::meridian_radius(latitude) * Earth::prime_vertical_radius(latitude)).sqrt()
+    }
+
+    /// Length of one degree of latitude at `latitude` on the ellipsoid (meters)
+    pub fn meters_per_degree_latitude(latitude: impl Into<Radians>) -> f64 {
+        Earth::meridian_radius(latitude) * 1_f64.to_radians()
+    }
+
+    /// Length of one degree of longitude at `latitude` on the ellipsoid (meters)
+    pub fn meters_per_degree_longitude(latitude: impl Into<Radians>) -> f64 {
+        let latitude = latitude.into();
+        Earth::prime_vertical_radius(latitude) * latitude.cos() * 1_f64.to_radians()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normal_gravity_matches_tr8350() {
+        assert!((Earth::normal_gravity(Degrees(0.), 0.) - 9.7803253359).abs() < 1e-10);
+        assert!((Earth::normal_gravity(Degrees(90.), 0.) - 9.8321849378).abs() < 1e-10);
+        assert!((Earth::normal_gravity(Degrees(-90.), 0.) - 9.8321849378).abs() < 1e-10);
+    }
+
+    #[test]
+    fn free_air_gradient() {
+        let gradient = |latitude: f64| (Earth::normal_gravity(Degrees(latitude), 10.) - Earth::normal_gravity(Degrees(latitude), 0.)) / 10.;
+        assert!((gradient(45.) + 3.086e-6).abs() < 2e-9, "{}", gradient(45.));
+        //Slightly steeper at the equator, slightly shallower at the poles
+        assert!(gradient(0.) < gradient(45.) && gradient(45.) < gradient(90.));
+        //The second-order term 3γh²/a² only adds about 7e-5 m/s² at 10 km
+        let linear = Earth::normal_gravity(Degrees(45.), 0.) + 10_000. * gradient(45.);
+        let second_order = Earth::normal_gravity(Degrees(45.), 10_000.) - linear;
+        assert!(second_order > 6e-5 && second_order < 8e-5, "{}", second_order);
+    }
+
+    #[test]
+    fn gravity_ned_points_down() {
+        let reference = Reference::new(LLA::from_degs(Degrees(45.), Degrees(7.), 100.));
+        let at_reference = reference.gravity_ned(Ned::new(0., 0., 0.));
+        assert_eq!((at_reference.north(), at_reference.east()), (0., 0.));
+        assert!((at_reference.down() as f64 - Earth::normal_gravity(Degrees(45.), 100.)).abs() < 1e-6);
+        //1 km up is about 0.3086 mGal/m × 1000 m lighter
+        let above = reference.gravity_ned(Ned::new(0., 0., -1000.));
+        assert!(((above.down() - at_reference.down()) as f64 + 3.086e-3).abs() < 2e-5);
+        let ecef = reference.gravity_ecef(Ned::new(0., 0., 0.));
+        assert!((ecef.as_vector().norm() - at_reference.down() as f64).abs() < 1e-5);
+    }
+
+    #[test]
+    fn radii_of_curvature() {
+        let (equator, pole) = (Degrees(0.), Degrees(90.));
+        assert!((Earth::meridian_radius(equator) - 6335439.327).abs() < 1e-3);
+        assert!((Earth::prime_vertical_radius(equator) - Earth::EQUATORIAL_RADIUS).abs() < 1e-9);
+        //Both equal the polar radius of curvature a²/b at the pole
+        assert!((Earth::meridian_radius(pole) - 6399593.626).abs() < 1e-3);
+        assert!((Earth::prime_vertical_radius(pole) - 6399593.626).abs() < 1e-3);
+        assert!((Earth::gaussian_radius(pole) - 6399593.626).abs() < 1e-3);
+        let latitude = Degrees(45.);
+        let gaussian = Earth::gaussian_radius(latitude);
+        assert!(Earth::meridian_radius(latitude) < gaussian && gaussian < Earth::prime_vertical_radius(latitude));
+        assert!((gaussian.powi(2) - Earth::meridian_radius(latitude) * Earth::prime_vertical_radius(latitude)).abs() < 1.);
+    }
+
+    #[test]
+    fn meters_per_degree_match_geodesics() {
+        for latitude in [0., 30., 45., -60., 89.] {
+            //Over 0.01° the geodesic and the arc along the parallel differ by well under a millimeter
+            let along_meridian = LLA::from_degs(Degrees(latitude - 0.005), Degrees(0.), 0.).distance_to(&LLA::from_degs(Degrees(latitude + 0.005), Degrees(0.), 0.)).unwrap();
+            let along_parallel = LLA::from_degs(Degrees(latitude), Degrees(-0.005), 0.).distance_to(&LLA::from_degs(Degrees(latitude), Degrees(0.005), 0.)).unwrap();
+            assert!((Earth::meters_per_degree_latitude(Degrees(latitude)) - 100. * along_meridian).abs() < 0.01, "{}", latitude);
+            assert!((Earth::meters_per_degree_longitude(Degrees(latitude)) - 100. * along_parallel).abs() < 0.01, "{}", latitude);
+        }
+        assert!((Earth::meters_per_degree_latitude(Degrees(0.)) - 110574.276).abs() < 1e-3);
+        assert!((Earth::meters_per_degree_longitude(Degrees(0.)) - 111319.491).abs() < 1e-3);
+        assert!(Earth::meters_per_degree_longitude(Degrees(90.)).abs() < 1e-9);
+    }
 }
@@ -3,6 +3,7 @@ use core::ops::{Add, Div, Mul, Neg, Sub};
 use crate::prelude::*;
 
 #[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ECEF {
     pub x: f64,
     pub y: f64,
@@ -44,19 +45,16 @@ impl ECEF {
         let altitude = (beta - Earth::EQUATORIAL_RADIUS * ti) * (latitude).cos()
             + (self.z - self.z.signum() * Earth::EQUATORIAL_RADIUS * SQRT_1_MINUS_ECCENTRICITY_SQUARED) * latitude.sin();
 
-        return LLA {
-            latitude,
-            longitude,
-            altitude: altitude as f32,
-        };
+        LLA::from_rads(Radians(latitude), Radians(longitude), altitude as f32)
     }
 
-    pub fn to_tangent(&self, reference: &Reference) -> Vector3<f32> {
+    pub fn to_tangent(&self, reference: &Reference) -> Ned<f32> {
         reference.ecef_to_tangent(*self)
     }
 
-    pub fn from_tangent(reference: &Reference, tangent: Vector3<f32>) -> ECEF {
-        reference.tangent_to_ecef(tangent)
+    /// Absolute position of a tangent plane point, see `Reference::tangent_to_ecef_position`
+    pub fn from_tangent_position(reference: &Reference, tangent: Ned<f32>) -> ECEF {
+        reference.tangent_to_ecef_position(tangent)
     }
 }
 
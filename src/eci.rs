@@ -0,0 +1,136 @@
+use core::ops::{Add, Sub};
+
+use nalgebra::Rotation3;
+
+use crate::prelude::*;
+
+/// Earth-centered inertial position (meters).
+///
+/// Simplified model: the frame is ECEF rotated back by the Earth rotation angle only, without precession,
+/// nutation or polar motion, which is adequate over the duration of a flight.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ECI {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+/// Rotation taking ECEF vectors into ECI at `utc_secs`
+fn ecef_to_eci_rotation(utc_secs: f64) -> Rotation3<f64> {
+    Rotation3::from_axis_angle(&Vector3::z_axis(), Earth::rotation_angle(utc_secs).0)
+}
+
+impl ECI {
+    pub fn new(x: f64, y: f64, z: f64) -> Self {
+        Self { x, y, z }
+    }
+
+    fn from_vector(v: Vector3<f64>) -> Self {
+        Self::new(v.x, v.y, v.z)
+    }
+
+    fn vector(&self) -> Vector3<f64> {
+        Vector3::new(self.x, self.y, self.z)
+    }
+
+    pub fn from_ecef(ecef: ECEF, utc_secs: f64) -> Self {
+        Self::from_vector(ecef_to_eci_rotation(utc_secs) * Vector3::new(ecef.x, ecef.y, ecef.z))
+    }
+
+    pub fn to_ecef(&self, utc_secs: f64) -> ECEF {
+        let v = ecef_to_eci_rotation(utc_secs).inverse() * self.vector();
+        ECEF::new(v.x, v.y, v.z)
+    }
+
+    /// Inertial velocity of a body at `position` moving at `velocity` relative to the Earth: `R (v + ω × r)`
+    pub fn velocity_from_ecef(position: ECEF, velocity: EcefVec, utc_secs: f64) -> EciVec {
+        let r = Vector3::new(position.x, position.y, position.z);
+        let v = ecef_to_eci_rotation(utc_secs) * (velocity.as_vector() + Earth::angular_velocity().as_vector().cross(&r));
+        EciVec::new(v.x, v.y, v.z)
+    }
+
+    /// Velocity relative to the Earth of a body at this position moving at inertial `velocity`: `Rᵀ v - ω × r`
+    pub fn velocity_to_ecef(&self, velocity: EciVec, utc_secs: f64) -> EcefVec {
+        let rotation = ecef_to_eci_rotation(utc_secs).inverse();
+        let r = rotation * self.vector();
+        let v = rotation * velocity.as_vector() - Earth::angular_velocity().as_vector().cross(&r);
+        EcefVec::new(v.x, v.y, v.z)
+    }
+}
+
+impl ECEF {
+    pub fn to_eci(&self, utc_secs: f64) -> ECI {
+        ECI::from_ecef(*self, utc_secs)
+    }
+}
+
+impl Add<EciVec> for ECI {
+    type Output = ECI;
+    fn add(self, rhs: EciVec) -> Self::Output {
+        ECI::from_vector(self.vector() + rhs.as_vector())
+    }
+}
+
+impl Sub<EciVec> for ECI {
+    type Output = ECI;
+    fn sub(self, rhs: EciVec) -> Self::Output {
+        ECI::from_vector(self.vector() - rhs.as_vector())
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::excessive_precision)]
+mod tests {
+    use super::*;
+
+    /// Unix time of a Julian day, UT1 taken as UTC
+    fn unix_secs(julian_day: f64) -> f64 {
+        (julian_day - 2440587.5) * 86400.
+    }
+
+    #[test]
+    fn earth_rotation_angle() {
+        //IERS Conventions (2010) eq. 5.15 at J2000.0
+        assert!((Earth::rotation_angle(unix_secs(2451545.0)).0 - 0.7790572732640 * core::f64::consts::TAU).abs() < 1e-9);
+        //SOFA iauEra00(2400000.5, 54388.0)
+        assert!((Earth::rotation_angle(unix_secs(2454388.5)).0 - 0.4022837240028158102).abs() < 1e-9);
+        //One sidereal day later the angle is back where it started
+        let t = unix_secs(2454388.5);
+        let sidereal_day = 86400. / 1.00273781191135448;
+        assert!((Earth::rotation_angle(t + sidereal_day).0 - Earth::rotation_angle(t).0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn greenwich_meridian_points_along_the_rotation_angle() {
+        let t = unix_secs(2454388.5);
+        let eci = ECEF::new(Earth::EQUATORIAL_RADIUS, 0., 1000.).to_eci(t);
+        let angle = Earth::rotation_angle(t).0;
+        assert!((eci.x - Earth::EQUATORIAL_RADIUS * angle.cos()).abs() < 1e-6);
+        assert!((eci.y - Earth::EQUATORIAL_RADIUS * angle.sin()).abs() < 1e-6);
+        assert_eq!(eci.z, 1000.);
+    }
+
+    #[test]
+    fn position_round_trip() {
+        let ecef = ECEF::new(1_113_194.9, -4_842_853.6, 3_985_144.3);
+        for t in [0., 1_700_000_000.25, unix_secs(2451545.0)] {
+            let back = ecef.to_eci(t).to_ecef(t);
+            assert!((back - ecef).x.abs() < 1e-6 && (back - ecef).y.abs() < 1e-6 && (back - ecef).z.abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn velocity_includes_earth_rotation() {
+        let t = 1_700_000_000.;
+        let position = ECEF::new(Earth::EQUATORIAL_RADIUS, 0., 0.);
+        //A point at rest on the equator moves eastwards at ω a in inertial space
+        let inertial = ECI::velocity_from_ecef(position, EcefVec::new(0., 0., 0.), t);
+        assert!((inertial.as_vector().norm() - Earth::ANGULAR_SPEED * Earth::EQUATORIAL_RADIUS).abs() < 1e-9);
+        assert!(inertial.as_vector().dot(&position.to_eci(t).vector()).abs() < 1e-3);
+
+        let velocity = EcefVec::new(12., -34., 5.6);
+        let back = position.to_eci(t).velocity_to_ecef(ECI::velocity_from_ecef(position, velocity, t), t);
+        assert!((back.as_vector() - velocity.as_vector()).norm() < 1e-9);
+    }
+}
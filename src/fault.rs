@@ -0,0 +1,307 @@
+//! Fault injection on the landing zone stream.
+//!
+//! Faults are written `kind[:value]@t=<secs>` to fire once, `t` seconds after the start, or
+//! `kind[:value]@p=<probability>` to fire at each update with that probability.
+
+use core::f64::consts::TAU;
+use core::fmt;
+use core::str::FromStr;
+
+use rand::Rng;
+
+use crate::prelude::*;
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq)]
+pub enum FaultError {
+    #[error("Cannot parse fault '{0}', expected kind[:value]@t=<secs> or kind[:value]@p=<probability>")]
+    Invalid(String),
+    #[error("Unknown fault kind '{0}', expected skip, drop, jump, outlier, freeze, nan or timestamp")]
+    UnknownKind(String),
+    #[error("Probability {0} is not in [0, 1]")]
+    InvalidProbability(f64),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FaultKind {
+    /// Do not send the next `n` updates
+    Skip(u32),
+    /// Offset all following positions by this many meters horizontally, in a random direction
+    Jump(f64),
+    /// Offset one position by this many meters horizontally, in a random direction
+    Outlier(f64),
+    /// Send the last position again for the next `n` updates, with fresh timestamps
+    Freeze(u32),
+    /// Send NaN position and velocity once
+    Nan,
+    /// Shift one timestamp by this many seconds, positive in the future
+    Timestamp(f64),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Trigger {
+    /// seconds after the start
+    At(f64),
+    /// chance per update
+    Probability(f64),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Fault {
+    pub kind: FaultKind,
+    pub trigger: Trigger,
+}
+
+impl fmt::Display for FaultKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FaultKind::Skip(1) => write!(f, "drop"),
+            FaultKind::Skip(n) => write!(f, "skip:{}", n),
+            FaultKind::Jump(meters) => write!(f, "jump:{}", meters),
+            FaultKind::Outlier(meters) => write!(f, "outlier:{}", meters),
+            FaultKind::Freeze(n) => write!(f, "freeze:{}", n),
+            FaultKind::Nan => write!(f, "nan"),
+            FaultKind::Timestamp(secs) => write!(f, "timestamp:{}", secs),
+        }
+    }
+}
+
+impl fmt::Display for Fault {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.trigger {
+            Trigger::At(time) => write!(f, "{}@t={}", self.kind, time),
+            Trigger::Probability(probability) => write!(f, "{}@p={}", self.kind, probability),
+        }
+    }
+}
+
+impl FromStr for FaultKind {
+    type Err = FaultError;
+
+    /// `skip:<n>`, `drop` (`skip:1`), `jump:<meters>`, `outlier:<meters>`, `freeze:<n>`, `nan`, `timestamp:<secs>`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || FaultError::Invalid(s.to_owned());
+        let (kind, value) = match s.split_once(':') {
+            Some((kind, value)) => (kind.trim(), Some(value.trim())),
+            None => (s.trim(), None),
+        };
+        let count = || value.ok_or_else(invalid)?.parse::<u32>().map_err(|_| invalid());
+        let number = || value.ok_or_else(invalid)?.parse::<f64>().map_err(|_| invalid());
+        match kind {
+            "skip" => Ok(FaultKind::Skip(count()?)),
+            "drop" if value.is_none() => Ok(FaultKind::Skip(1)),
+            "jump" => Ok(FaultKind::Jump(number()?)),
+            "outlier" => Ok(FaultKind::Outlier(number()?)),
+            "freeze" => Ok(FaultKind::Freeze(count()?)),
+            "nan" if value.is_none() => Ok(FaultKind::Nan),
+            "timestamp" => Ok(FaultKind::Timestamp(number()?)),
+            "drop" | "nan" => Err(invalid()),
+            _ => Err(FaultError::UnknownKind(kind.to_owned())),
+        }
+    }
+}
+
+impl FromStr for Fault {
+    type Err = FaultError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || FaultError::Invalid(s.to_owned());
+        let (kind, trigger) = s.rsplit_once('@').ok_or_else(invalid)?;
+        let (key, value) = trigger.split_once('=').ok_or_else(invalid)?;
+        let value: f64 = value.trim().parse().map_err(|_| invalid())?;
+        let trigger = match key.trim() {
+            "t" => Trigger::At(value),
+            "p" if (0. ..=1.).contains(&value) => Trigger::Probability(value),
+            "p" => return Err(FaultError::InvalidProbability(value)),
+            _ => return Err(invalid()),
+        };
+        Ok(Self {
+            kind: kind.parse().map_err(|e| match e {
+                FaultError::Invalid(_) => invalid(),
+                e => e,
+            })?,
+            trigger,
+        })
+    }
+}
+
+/// A landing zone message
+#[derive(Clone, Copy, Debug)]
+pub struct Update {
+    pub lla: LLA,
+    pub velocity: Ned<f32>,
+    pub timestamp: f64,
+}
+
+/// Applies faults to the stream of updates
+#[derive(Default)]
+pub struct FaultInjector {
+    faults: Vec<Fault>,
+    /// one-shot faults already fired
+    fired: Vec<bool>,
+    skip_remaining: u32,
+    freeze_remaining: u32,
+    last_sent: Option<LLA>,
+    jump: Ned<f32>,
+}
+
+fn random_offset(meters: f64, rng: &mut impl Rng) -> Ned<f32> {
+    let (sin, cos) = rng.random_range(0_f64..TAU).sin_cos();
+    Ned::new((meters * cos) as f32, (meters * sin) as f32, 0.)
+}
+
+impl FaultInjector {
+    pub fn new(faults: Vec<Fault>) -> Self {
+        Self {
+            fired: vec![false; faults.len()],
+            faults,
+            ..Default::default()
+        }
+    }
+
+    /// Faults firing at `time` (seconds after the start)
+    fn triggered(&mut self, time: f64, rng: &mut impl Rng) -> Vec<FaultKind> {
+        let mut triggered = Vec::new();
+        for (fault, fired) in self.faults.iter().zip(self.fired.iter_mut()) {
+            let fires = match fault.trigger {
+                Trigger::At(at) => !*fired && time >= at,
+                Trigger::Probability(probability) => rng.random_bool(probability),
+            };
+            if fires {
+                *fired = true;
+                triggered.push(fault.kind);
+            }
+        }
+        triggered
+    }
+
+    /// The update to send instead of `update`, if any, and the faults that fired
+    pub fn apply(&mut self, time: f64, update: Update, rng: &mut impl Rng) -> (Option<Update>, Vec<FaultKind>) {
+        let triggered = self.triggered(time, rng);
+        let mut update = update;
+        if self.jump != Ned::default() {
+            update.lla = update.lla.offset(self.jump);
+        }
+        for kind in &triggered {
+            match *kind {
+                FaultKind::Skip(n) => self.skip_remaining = self.skip_remaining.max(n),
+                FaultKind::Jump(meters) => {
+                    let jump = random_offset(meters, rng);
+                    self.jump += jump;
+                    update.lla = update.lla.offset(jump);
+                }
+                FaultKind::Outlier(meters) => update.lla = update.lla.offset(random_offset(meters, rng)),
+                FaultKind::Freeze(n) => self.freeze_remaining = self.freeze_remaining.max(n),
+                FaultKind::Nan => {
+                    update.lla = LLA::from_rads(Radians(f64::NAN), Radians(f64::NAN), f32::NAN);
+                    update.velocity = Ned::new(f32::NAN, f32::NAN, f32::NAN);
+                }
+                FaultKind::Timestamp(secs) => update.timestamp += secs,
+            }
+        }
+
+        //A freeze waits for a position to repeat
+        if let (true, Some(last_sent)) = (self.freeze_remaining > 0, self.last_sent) {
+            self.freeze_remaining -= 1;
+            update.lla = last_sent;
+        }
+        if self.skip_remaining > 0 {
+            self.skip_remaining -= 1;
+            return (None, triggered);
+        }
+        //Corrupted positions are never repeated by a freeze
+        if LLA::new(update.lla.lat(), update.lla.lon(), update.lla.altitude).is_ok() {
+            self.last_sent = Some(update.lla);
+        }
+        (Some(update), triggered)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    fn update(north: f32, timestamp: f64) -> Update {
+        Update {
+            lla: LLA::from_degs(Degrees(45.), Degrees(-73.), 10.).offset(Ned::new(north, 0., 0.)),
+            velocity: Ned::new(1., 0., 0.),
+            timestamp,
+        }
+    }
+
+    fn injector(faults: &[&str]) -> FaultInjector {
+        FaultInjector::new(faults.iter().map(|f| f.parse().unwrap()).collect())
+    }
+
+    /// Position sent at each time, `None` when skipped
+    fn run(injector: &mut FaultInjector, times: impl IntoIterator<Item = u32>) -> Vec<Option<LLA>> {
+        let mut rng = ChaCha8Rng::seed_from_u64(3);
+        times.into_iter().map(|t| injector.apply(t as f64, update(t as f32, t as f64), &mut rng).0.map(|u| u.lla)).collect()
+    }
+
+    fn north_of(lla: Option<LLA>) -> Option<f32> {
+        lla.map(|lla| LLA::from_degs(Degrees(45.), Degrees(-73.), 10.).offset_to(&lla).north().round())
+    }
+
+    #[test]
+    fn parses_and_displays() {
+        for text in ["skip:3@t=10", "drop@p=0.5", "jump:25@t=1.5", "outlier:30@p=0.01", "freeze:4@t=2", "nan@t=7", "timestamp:-0.5@t=3"] {
+            assert_eq!(text.parse::<Fault>().unwrap().to_string(), text);
+        }
+        assert_eq!("skip:1@t=2".parse::<Fault>().unwrap().to_string(), "drop@t=2");
+        assert_eq!("drop@p=1.5".parse::<Fault>(), Err(FaultError::InvalidProbability(1.5)));
+        assert_eq!("wobble@t=1".parse::<Fault>(), Err(FaultError::UnknownKind("wobble".to_owned())));
+        for text in ["skip@t=1", "nan:3@t=1", "jump:1", "jump:1@x=2", "freeze:-1@t=1"] {
+            assert_eq!(text.parse::<Fault>(), Err(FaultError::Invalid(text.to_owned())), "{}", text);
+        }
+    }
+
+    #[test]
+    fn skip_and_freeze() {
+        let sent = run(&mut injector(&["skip:2@t=1", "freeze:2@t=4"]), 0..8);
+        let norths: Vec<Option<f32>> = sent.into_iter().map(north_of).collect();
+        assert_eq!(norths, [Some(0.), None, None, Some(3.), Some(3.), Some(3.), Some(6.), Some(7.)]);
+    }
+
+    #[test]
+    fn freeze_waits_for_a_position() {
+        //Nothing was sent when the freeze fires, it starts with the first sent position
+        let norths: Vec<Option<f32>> = run(&mut injector(&["skip:2@t=0", "freeze:2@t=0"]), 0..6).into_iter().map(north_of).collect();
+        assert_eq!(norths, [None, None, Some(2.), Some(2.), Some(2.), Some(5.)]);
+    }
+
+    #[test]
+    fn nan_is_not_frozen() {
+        let sent = run(&mut injector(&["nan@t=1", "freeze:2@t=2"]), 0..5);
+        assert!(sent[1].unwrap().lat().0.is_nan());
+        let norths: Vec<Option<f32>> = sent.into_iter().map(north_of).collect();
+        assert_eq!(norths[2..], [Some(0.), Some(0.), Some(4.)]);
+    }
+
+    #[test]
+    fn jump_persists_and_outlier_does_not() {
+        let mut rng = ChaCha8Rng::seed_from_u64(3);
+        let mut injector = injector(&["jump:100@t=1", "outlier:50@t=3"]);
+        let offsets: Vec<f32> = (0..5)
+            .map(|t| {
+                let sent = injector.apply(t as f64, update(0., t as f64), &mut rng).0.unwrap();
+                let offset = update(0., 0.).lla.offset_to(&sent.lla);
+                offset.north().hypot(offset.east())
+            })
+            .collect();
+        assert!(offsets[0] < 0.01);
+        for offset in [offsets[1], offsets[2], offsets[4]] {
+            assert!((offset - 100.).abs() < 0.1, "{:?}", offsets);
+        }
+        assert!((offsets[3] - 100.).abs() > 0.1 && offsets[3] < 150.1, "{:?}", offsets);
+    }
+
+    #[test]
+    fn timestamp_shifts_one_update() {
+        let mut rng = ChaCha8Rng::seed_from_u64(3);
+        let mut injector = injector(&["timestamp:-0.25@t=1"]);
+        let timestamps: Vec<f64> = (0..3).map(|t| injector.apply(t as f64, update(0., t as f64), &mut rng).0.unwrap().timestamp).collect();
+        assert_eq!(timestamps, [0., 0.75, 2.]);
+    }
+}
@@ -0,0 +1,382 @@
+use core::fmt;
+use core::str::FromStr;
+
+use crate::prelude::*;
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq)]
+pub enum ParseCoordinateError {
+    #[error("Cannot parse coordinate '{0}'")]
+    Invalid(String),
+    #[error(transparent)]
+    Coordinate(#[from] LLAError),
+}
+
+/// Human-readable coordinate notations
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum CoordinateFormat {
+    /// `45.5033611, -73.2500000`
+    #[default]
+    DecimalDegrees,
+    /// `45°30'12.10"N, 73°15'00.00"W`
+    Dms,
+    /// `45°30.2017'N, 73°15.0000'W`
+    Ddm,
+    /// `4530.2017,N,07315.0000,W`
+    Nmea,
+}
+
+impl CoordinateFormat {
+    /// Decimals of the last component (degrees, seconds or minutes) when none is requested
+    fn default_precision(&self) -> usize {
+        match self {
+            CoordinateFormat::DecimalDegrees => 7,
+            CoordinateFormat::Dms => 2,
+            CoordinateFormat::Ddm | CoordinateFormat::Nmea => 4,
+        }
+    }
+
+    /// Units of the last component per degree
+    fn units_per_degree(&self) -> f64 {
+        match self {
+            CoordinateFormat::DecimalDegrees => 1.,
+            CoordinateFormat::Dms => 3600.,
+            CoordinateFormat::Ddm | CoordinateFormat::Nmea => 60.,
+        }
+    }
+}
+
+/// `Display` adapter returned by `LLA::format`
+pub struct LLAFormatter<'a> {
+    lla: &'a LLA,
+    format: CoordinateFormat,
+    precision: Option<usize>,
+}
+
+impl LLAFormatter<'_> {
+    pub fn precision(mut self, precision: usize) -> Self {
+        self.precision = Some(precision);
+        self
+    }
+}
+
+/// Splits an absolute angle into whole degrees, whole minutes and remaining seconds (or minutes when
+/// `with_seconds` is false), rounded at `precision` decimals with the carry propagated
+fn sexagesimal(degrees: f64, with_seconds: bool, precision: usize) -> (u64, u64, f64) {
+    let scale = 10_f64.powi(precision as i32);
+    let units_per_degree = if with_seconds { 3600. } else { 60. } * scale;
+    let total = (degrees.abs() * units_per_degree).round();
+    let whole_degrees = (total / units_per_degree).floor();
+    let rest = total - whole_degrees * units_per_degree;
+    if with_seconds {
+        let minutes = (rest / (60. * scale)).floor();
+        (whole_degrees as u64, minutes as u64, (rest - minutes * 60. * scale) / scale)
+    } else {
+        (whole_degrees as u64, 0, rest / scale)
+    }
+}
+
+fn hemisphere(value: f64, positive: char, negative: char) -> char {
+    if value < 0. { negative } else { positive }
+}
+
+/// 0 for an angle that rounds to zero at the given precision, so that it shows no minus sign or S/W hemisphere
+fn zero_if_rounded(degrees: f64, units_per_degree: f64, precision: usize) -> f64 {
+    if (degrees.abs() * units_per_degree * 10_f64.powi(precision as i32)).round() == 0. { 0. } else { degrees }
+}
+
+impl fmt::Display for LLAFormatter<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let precision = self.precision.unwrap_or(self.format.default_precision());
+        let units_per_degree = self.format.units_per_degree();
+        let lat = zero_if_rounded(self.lla.lat().to_degrees().0, units_per_degree, precision);
+        let lon = zero_if_rounded(self.lla.lon().to_degrees().0, units_per_degree, precision);
+        let alt = self.lla.altitude;
+        //Seconds or minutes field width, including the two integer digits and the decimal point
+        let width = if precision > 0 { precision + 3 } else { 2 };
+        match self.format {
+            CoordinateFormat::DecimalDegrees => write!(f, "{:.*}, {:.*}, {:.2}", precision, lat, precision, lon, alt),
+            CoordinateFormat::Dms => {
+                let (lat_d, lat_m, lat_s) = sexagesimal(lat, true, precision);
+                let (lon_d, lon_m, lon_s) = sexagesimal(lon, true, precision);
+                write!(
+                    f,
+                    "{}°{:02}'{:0width$.precision$}\"{}, {}°{:02}'{:0width$.precision$}\"{}, {:.2}",
+                    lat_d,
+                    lat_m,
+                    lat_s,
+                    hemisphere(lat, 'N', 'S'),
+                    lon_d,
+                    lon_m,
+                    lon_s,
+                    hemisphere(lon, 'E', 'W'),
+                    alt,
+                )
+            }
+            CoordinateFormat::Ddm => {
+                let (lat_d, _, lat_m) = sexagesimal(lat, false, precision);
+                let (lon_d, _, lon_m) = sexagesimal(lon, false, precision);
+                write!(
+                    f,
+                    "{}°{:0width$.precision$}'{}, {}°{:0width$.precision$}'{}, {:.2}",
+                    lat_d,
+                    lat_m,
+                    hemisphere(lat, 'N', 'S'),
+                    lon_d,
+                    lon_m,
+                    hemisphere(lon, 'E', 'W'),
+                    alt,
+                )
+            }
+            CoordinateFormat::Nmea => {
+                let (lat_d, _, lat_m) = sexagesimal(lat, false, precision);
+                let (lon_d, _, lon_m) = sexagesimal(lon, false, precision);
+                write!(
+                    f,
+                    "{:02}{:0width$.precision$},{},{:03}{:0width$.precision$},{},{:.2}",
+                    lat_d,
+                    lat_m,
+                    hemisphere(lat, 'N', 'S'),
+                    lon_d,
+                    lon_m,
+                    hemisphere(lon, 'E', 'W'),
+                    alt,
+                )
+            }
+        }
+    }
+}
+
+/// Decimal degrees, comma separated. The formatter precision, if any, applies to latitude and longitude.
+impl fmt::Display for LLA {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match f.precision() {
+            Some(precision) => write!(f, "{}", self.format(CoordinateFormat::DecimalDegrees).precision(precision)),
+            None => write!(f, "{}, {}, {}", self.lat().to_degrees(), self.lon().to_degrees(), self.altitude),
+        }
+    }
+}
+
+/// Parses a single angle in decimal degrees, DMS or DDM, signed or with a hemisphere letter before or after
+fn parse_angle(text: &str, positive: char, negative: char) -> Option<Degrees> {
+    let mut text = text.trim();
+    let mut sign = 1.;
+    for letter in [positive, negative] {
+        let stripped = text
+            .strip_prefix(letter)
+            .or_else(|| text.strip_suffix(letter))
+            .or_else(|| text.strip_prefix(letter.to_ascii_lowercase()))
+            .or_else(|| text.strip_suffix(letter.to_ascii_lowercase()));
+        if let Some(stripped) = stripped {
+            text = stripped.trim();
+            if letter == negative {
+                sign = -1.;
+            }
+            break;
+        }
+    }
+    let (signed, text) = match text.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, text.strip_prefix('+').unwrap_or(text)),
+    };
+    if signed {
+        //A sign and a southern or western hemisphere letter together are ambiguous
+        if sign < 0. {
+            return None;
+        }
+        sign = -1.;
+    }
+
+    let components = text
+        .split(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .filter(|c| !c.is_empty())
+        .map(|c| c.parse::<f64>().ok())
+        .collect::<Option<Vec<f64>>>()?;
+    //Only the symbols of a sexagesimal angle may separate its components
+    if text.chars().any(|c| !(c.is_ascii_digit() || c == '.' || c.is_whitespace() || ANGLE_SYMBOLS.contains(&c))) {
+        return None;
+    }
+    let degrees = match components.as_slice() {
+        [d] => *d,
+        [d, m] if *m < 60. && d.fract() == 0. => d + m / 60.,
+        [d, m, s] if *m < 60. && *s < 60. && d.fract() == 0. && m.fract() == 0. => d + m / 60. + s / 3600.,
+        _ => return None,
+    };
+    Some(Degrees(sign * degrees))
+}
+
+/// NMEA `ddmm.mmmm` / `dddmm.mmmm` with its hemisphere field
+fn parse_nmea_angle(value: &str, hemisphere: &str, positive: &str, negative: &str) -> Option<Degrees> {
+    let value: f64 = value.trim().parse().ok()?;
+    let degrees = (value / 100.).trunc();
+    let minutes = value - degrees * 100.;
+    if !(0. ..60.).contains(&minutes) {
+        return None;
+    }
+    let sign = match hemisphere.trim() {
+        h if h.eq_ignore_ascii_case(positive) => 1.,
+        h if h.eq_ignore_ascii_case(negative) => -1.,
+        _ => return None,
+    };
+    Some(Degrees(sign * (degrees + minutes / 60.)))
+}
+
+fn parse_altitude(text: Option<&str>) -> Option<f32> {
+    match text.map(str::trim) {
+        None | Some("") => Some(0.),
+        Some(text) => text.strip_suffix('m').unwrap_or(text).trim().parse().ok(),
+    }
+}
+
+/// Symbols that may end a sexagesimal angle
+const ANGLE_SYMBOLS: [char; 6] = ['°', '\'', '"', '′', '″', 'º'];
+
+/// Splits `45°30'12"N 73°15'W 120` after the latitude and longitude hemisphere letters, or
+/// `N45°30'12" W73°15' 120` before them
+fn split_at_hemispheres(text: &str) -> Option<(&str, &str, &str)> {
+    let text = text.trim();
+    let lat_letter = text.find(['N', 'S', 'n', 's'])?;
+    let lon_letter = lat_letter + 1 + text[lat_letter + 1..].find(['E', 'W', 'e', 'w'])?;
+    if lat_letter > 0 {
+        return Some((&text[..=lat_letter], &text[lat_letter + 1..=lon_letter], &text[lon_letter + 1..]));
+    }
+    //Prefixed: the longitude ends after its last angle symbol, or at the first whitespace without symbols
+    let value = text[lon_letter + 1..].trim_start();
+    let value_start = text.len() - value.len();
+    let value_len = match value.rfind(ANGLE_SYMBOLS) {
+        Some(symbol) => symbol + value[symbol..].chars().next()?.len_utf8(),
+        None => value.find(char::is_whitespace).unwrap_or(value.len()),
+    };
+    let lon_end = value_start + value_len;
+    Some((&text[..lon_letter], &text[lon_letter..lon_end], &text[lon_end..]))
+}
+
+/// Accepts `lat, lon[, alt]` in decimal degrees, DMS (`45°30'12.3"N`) or DDM (`45°30.205'N`), whitespace separated
+/// when hemisphere letters are used, as well as NMEA fields `ddmm.mmmm,N,dddmm.mmmm,W[,alt]`.
+/// A missing altitude is 0.
+impl FromStr for LLA {
+    type Err = ParseCoordinateError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || ParseCoordinateError::Invalid(s.to_owned());
+        let fields: Vec<&str> = s.split([',', ';']).map(str::trim).collect();
+
+        let (lat, lon, alt) = if fields.len() >= 4 && fields.len() <= 5 && fields[1].len() == 1 && fields[3].len() == 1 {
+            (
+                parse_nmea_angle(fields[0], fields[1], "N", "S"),
+                parse_nmea_angle(fields[2], fields[3], "E", "W"),
+                parse_altitude(fields.get(4).copied()),
+            )
+        } else if fields.len() >= 2 && fields.len() <= 3 {
+            (
+                parse_angle(fields[0], 'N', 'S'),
+                parse_angle(fields[1], 'E', 'W'),
+                parse_altitude(fields.get(2).copied()),
+            )
+        } else if let Some((lat, lon, alt)) = split_at_hemispheres(s) {
+            (parse_angle(lat, 'N', 'S'), parse_angle(lon, 'E', 'W'), parse_altitude(Some(alt)))
+        } else {
+            let mut tokens = s.split_whitespace();
+            let (lat, lon, alt) = (tokens.next(), tokens.next(), tokens.next());
+            if tokens.next().is_some() {
+                return Err(invalid());
+            }
+            (
+                lat.and_then(|t| parse_angle(t, 'N', 'S')),
+                lon.and_then(|t| parse_angle(t, 'E', 'W')),
+                parse_altitude(alt),
+            )
+        };
+
+        match (lat, lon, alt) {
+            (Some(lat), Some(lon), Some(alt)) => Ok(LLA::new(lat, lon, alt)?),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl LLA {
+    /// Formats in one of the common notations, e.g. `lla.format(CoordinateFormat::Dms).precision(1)`
+    pub fn format(&self, format: CoordinateFormat) -> LLAFormatter<'_> {
+        LLAFormatter {
+            lla: self,
+            format,
+            precision: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lla::lla;
+
+    fn assert_close(actual: &LLA, expected: &LLA, tolerance: f64) {
+        let error = (actual.lat().to_degrees().0 - expected.lat().to_degrees().0)
+            .abs()
+            .max((actual.lon().to_degrees() - expected.lon().to_degrees()).wrap_180().0.abs());
+        assert!(error <= tolerance, "{} instead of {}", actual, expected);
+        assert!((actual.altitude - expected.altitude).abs() < 0.01, "{} instead of {}", actual, expected);
+    }
+
+    #[test]
+    fn display_round_trips() {
+        //Half a unit of the last printed decimal
+        let formats = [
+            (CoordinateFormat::DecimalDegrees, 0.5e-7),
+            (CoordinateFormat::Dms, 0.005 / 3600.),
+            (CoordinateFormat::Ddm, 0.00005 / 60.),
+            (CoordinateFormat::Nmea, 0.00005 / 60.),
+        ];
+        let points = [lla(45.5033611, -73.25, 20.5), lla(-33.8567844, 151.2152967, 0.), lla(0.5, -0.5, -10.), lla(-89.99, 179.99, 1000.)];
+        for (format, tolerance) in formats {
+            for point in points {
+                let text = point.format(format).to_string();
+                let parsed: LLA = text.parse().unwrap_or_else(|e| panic!("{:?} '{}': {}", format, text, e));
+                assert_close(&parsed, &point, tolerance + 1e-12);
+            }
+        }
+        for point in points {
+            assert_close(&point.to_string().parse().unwrap(), &point, 1e-12);
+        }
+    }
+
+    #[test]
+    fn formats() {
+        let point = lla(45.5033611, -73.25, 20.);
+        assert_eq!(point.format(CoordinateFormat::DecimalDegrees).to_string(), "45.5033611, -73.2500000, 20.00");
+        assert_eq!(point.format(CoordinateFormat::Dms).to_string(), "45°30'12.10\"N, 73°15'00.00\"W, 20.00");
+        assert_eq!(point.format(CoordinateFormat::Ddm).to_string(), "45°30.2017'N, 73°15.0000'W, 20.00");
+        assert_eq!(point.format(CoordinateFormat::Nmea).to_string(), "4530.2017,N,07315.0000,W,20.00");
+        //Rounding carries into the minutes and degrees
+        assert_eq!(lla(10.9999999, 0., 0.).format(CoordinateFormat::Dms).to_string(), "11°00'00.00\"N, 0°00'00.00\"E, 0.00");
+    }
+
+    #[test]
+    fn rounded_zero_has_no_sign_or_southern_hemisphere() {
+        let point = lla(-1e-9, -1e-9, 0.);
+        assert_eq!(point.format(CoordinateFormat::DecimalDegrees).to_string(), "0.0000000, 0.0000000, 0.00");
+        assert_eq!(point.format(CoordinateFormat::Dms).to_string(), "0°00'00.00\"N, 0°00'00.00\"E, 0.00");
+        assert_eq!(point.format(CoordinateFormat::Nmea).to_string(), "0000.0000,N,00000.0000,E,0.00");
+        assert_eq!(lla(-0.01, 0., 0.).format(CoordinateFormat::Dms).to_string(), "0°00'36.00\"S, 0°00'00.00\"E, 0.00");
+    }
+
+    #[test]
+    fn hemisphere_letters_before_or_after() {
+        let expected = lla(45.5, -73.25, 0.);
+        for text in ["45.5N 73.25W", "N45.5 W73.25", "N 45.5 W 73.25", "n45.5 w73.25", "45°30'N 73°15'W", "N45°30' W73°15'", "N45.5, W73.25"] {
+            assert_close(&text.parse().unwrap_or_else(|e| panic!("'{}': {}", text, e)), &expected, 1e-12);
+        }
+        let with_altitude = lla(45.5, -73.25, 120.);
+        for text in ["45°30'00\"N 73°15'00\"W 120", "N45°30'00\" W73°15'00\" 120", "N45.5 W73.25 120m"] {
+            assert_close(&text.parse().unwrap_or_else(|e| panic!("'{}': {}", text, e)), &with_altitude, 1e-12);
+        }
+        assert_close(&"S33.5 E151.25".parse().unwrap(), &lla(-33.5, 151.25, 0.), 1e-12);
+    }
+
+    #[test]
+    fn rejects_invalid() {
+        for text in ["", "45.5", "91, 0", "-45.5S, 73W", "45°61'N 73°W", "45.5, 73.25, 1, 2", "abc, def"] {
+            assert!(text.parse::<LLA>().is_err(), "'{}'", text);
+        }
+    }
+}
@@ -0,0 +1,158 @@
+use core::ops::{Add, AddAssign, Div, Mul, Neg, Sub, SubAssign};
+
+use nalgebra::Scalar;
+
+use crate::prelude::*;
+
+/// Declares a frame-tagged 3D vector. The wrapped `Vector3` is private so that a vector cannot be relabeled
+/// into another frame, it is readable through `as_vector` for linear algebra. Arithmetic between two different
+/// frames does not type-check.
+macro_rules! frame_vector {
+    ($(#[$meta:meta])* $name:ident<$default:ty>, $x:ident, $y:ident, $z:ident) => {
+        $(#[$meta])*
+        #[derive(Clone, Copy, Debug, PartialEq, Default)]
+        #[repr(transparent)]
+        pub struct $name<T: Scalar = $default>(Vector3<T>);
+
+        impl<T: Scalar> $name<T> {
+            pub fn new($x: T, $y: T, $z: T) -> Self {
+                Self(Vector3::new($x, $y, $z))
+            }
+
+            pub fn as_vector(&self) -> &Vector3<T> {
+                &self.0
+            }
+
+            pub fn $x(&self) -> T {
+                self.0.x.clone()
+            }
+
+            pub fn $y(&self) -> T {
+                self.0.y.clone()
+            }
+
+            pub fn $z(&self) -> T {
+                self.0.z.clone()
+            }
+        }
+
+        impl<T: Scalar> Add for $name<T>
+        where
+            Vector3<T>: Add<Output = Vector3<T>>,
+        {
+            type Output = Self;
+            fn add(self, rhs: Self) -> Self::Output {
+                Self(self.0 + rhs.0)
+            }
+        }
+
+        impl<T: Scalar> Sub for $name<T>
+        where
+            Vector3<T>: Sub<Output = Vector3<T>>,
+        {
+            type Output = Self;
+            fn sub(self, rhs: Self) -> Self::Output {
+                Self(self.0 - rhs.0)
+            }
+        }
+
+        impl<T: Scalar> AddAssign for $name<T>
+        where
+            Vector3<T>: AddAssign,
+        {
+            fn add_assign(&mut self, rhs: Self) {
+                self.0 += rhs.0;
+            }
+        }
+
+        impl<T: Scalar> SubAssign for $name<T>
+        where
+            Vector3<T>: SubAssign,
+        {
+            fn sub_assign(&mut self, rhs: Self) {
+                self.0 -= rhs.0;
+            }
+        }
+
+        impl<T: Scalar> Mul<T> for $name<T>
+        where
+            Vector3<T>: Mul<T, Output = Vector3<T>>,
+        {
+            type Output = Self;
+            fn mul(self, rhs: T) -> Self::Output {
+                Self(self.0 * rhs)
+            }
+        }
+
+        impl<T: Scalar> Div<T> for $name<T>
+        where
+            Vector3<T>: Div<T, Output = Vector3<T>>,
+        {
+            type Output = Self;
+            fn div(self, rhs: T) -> Self::Output {
+                Self(self.0 / rhs)
+            }
+        }
+
+        impl<T: Scalar> Neg for $name<T>
+        where
+            Vector3<T>: Neg<Output = Vector3<T>>,
+        {
+            type Output = Self;
+            fn neg(self) -> Self::Output {
+                Self(-self.0)
+            }
+        }
+    };
+}
+
+frame_vector!(
+    /// North-East-Down vector in the tangent plane of a `Reference`
+    Ned<f32>, north, east, down
+);
+
+frame_vector!(
+    /// East-North-Up vector in the tangent plane of a `Reference`
+    Enu<f32>, east, north, up
+);
+
+frame_vector!(
+    /// Forward-Right-Down vector in a vehicle or sensor body frame
+    Body<f32>, forward, right, down
+);
+
+frame_vector!(
+    /// Displacement or rate in the Earth-centered Earth-fixed frame (meters, not a position)
+    EcefVec<f64>, x, y, z
+);
+
+frame_vector!(
+    /// Displacement or rate in the Earth-centered inertial frame (meters, not a position)
+    EciVec<f64>, x, y, z
+);
+
+impl<T: Scalar + Neg<Output = T>> Ned<T> {
+    pub fn to_enu(&self) -> Enu<T> {
+        Enu::new(self.east(), self.north(), -self.down())
+    }
+}
+
+impl<T: Scalar + Neg<Output = T>> Enu<T> {
+    pub fn to_ned(&self) -> Ned<T> {
+        Ned::new(self.north(), self.east(), -self.up())
+    }
+}
+
+impl Add<EcefVec> for ECEF {
+    type Output = ECEF;
+    fn add(self, rhs: EcefVec) -> Self::Output {
+        ECEF::new(self.x + rhs.x(), self.y + rhs.y(), self.z + rhs.z())
+    }
+}
+
+impl Sub<EcefVec> for ECEF {
+    type Output = ECEF;
+    fn sub(self, rhs: EcefVec) -> Self::Output {
+        ECEF::new(self.x - rhs.x(), self.y - rhs.y(), self.z - rhs.z())
+    }
+}
@@ -0,0 +1,270 @@
+use core::f64::consts::PI;
+
+use crate::prelude::*;
+
+#[derive(thiserror::Error, Debug, Clone, Copy, PartialEq)]
+pub enum GeodesicError {
+    /// Vincenty's inverse iteration does not converge for nearly antipodal points
+    #[error("Geodesic inverse did not converge (points are nearly antipodal)")]
+    NoConvergence,
+}
+
+/// Solution of the inverse geodesic problem between two points
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Geodesic {
+    /// meters along the WGS-84 ellipsoid
+    pub distance: f64,
+    /// azimuth at the start point, clockwise from north
+    pub initial_bearing: Radians,
+    /// azimuth at the end point, clockwise from north
+    pub final_bearing: Radians,
+}
+
+const POLAR_RADIUS: f64 = Earth::EQUATORIAL_RADIUS * (1. - Earth::FLATNESS);
+const MAX_ITERATIONS: usize = 200;
+const TOLERANCE: f64 = 1e-12;
+
+/// Vincenty's A and B series coefficients for a given cos²α
+fn series_coefficients(cos_squared_alpha: f64) -> (f64, f64) {
+    let a2 = Earth::EQUATORIAL_RADIUS * Earth::EQUATORIAL_RADIUS;
+    let b2 = POLAR_RADIUS * POLAR_RADIUS;
+    let u2 = cos_squared_alpha * (a2 - b2) / b2;
+    let a = 1. + u2 / 16384. * (4096. + u2 * (-768. + u2 * (320. - 175. * u2)));
+    let b = u2 / 1024. * (256. + u2 * (-128. + u2 * (74. - 47. * u2)));
+    (a, b)
+}
+
+fn delta_sigma(b: f64, sin_sigma: f64, cos_sigma: f64, cos_2sigma_m: f64) -> f64 {
+    let cos2 = cos_2sigma_m * cos_2sigma_m;
+    b * sin_sigma
+        * (cos_2sigma_m
+            + b / 4.
+                * (cos_sigma * (-1. + 2. * cos2)
+                    - b / 6. * cos_2sigma_m * (-3. + 4. * sin_sigma * sin_sigma) * (-3. + 4. * cos2)))
+}
+
+fn lambda_correction(c: f64, sin_alpha: f64, sigma: f64, sin_sigma: f64, cos_sigma: f64, cos_2sigma_m: f64) -> f64 {
+    (1. - c) * Earth::FLATNESS * sin_alpha * (sigma + c * sin_sigma * (cos_2sigma_m + c * cos_sigma * (-1. + 2. * cos_2sigma_m * cos_2sigma_m)))
+}
+
+fn c_coefficient(cos_squared_alpha: f64) -> f64 {
+    Earth::FLATNESS / 16. * cos_squared_alpha * (4. + Earth::FLATNESS * (4. - 3. * cos_squared_alpha))
+}
+
+/// Reduced latitude (sin, cos)
+fn reduced_latitude(latitude: f64) -> (f64, f64) {
+    let u = ((1. - Earth::FLATNESS) * latitude.tan()).atan();
+    (u.sin(), u.cos())
+}
+
+impl LLA {
+    /// Vincenty's inverse solution on the WGS-84 ellipsoid, altitudes are ignored
+    pub fn geodesic_to(&self, other: &LLA) -> Result<Geodesic, GeodesicError> {
+        let l = Radians(other.lon().0 - self.lon().0).wrap_pi().0;
+        let (sin_u1, cos_u1) = reduced_latitude(self.lat().0);
+        let (sin_u2, cos_u2) = reduced_latitude(other.lat().0);
+
+        let mut lambda = l;
+        for _ in 0..MAX_ITERATIONS {
+            let (sin_lambda, cos_lambda) = lambda.sin_cos();
+            let sin_sigma = ((cos_u2 * sin_lambda).powi(2) + (cos_u1 * sin_u2 - sin_u1 * cos_u2 * cos_lambda).powi(2)).sqrt();
+            if sin_sigma == 0. {
+                return Ok(Geodesic {
+                    distance: 0.,
+                    initial_bearing: Radians(0.),
+                    final_bearing: Radians(0.),
+                });
+            }
+            let cos_sigma = sin_u1 * sin_u2 + cos_u1 * cos_u2 * cos_lambda;
+            let sigma = sin_sigma.atan2(cos_sigma);
+            let sin_alpha = cos_u1 * cos_u2 * sin_lambda / sin_sigma;
+            let cos_squared_alpha = 1. - sin_alpha * sin_alpha;
+            //Equatorial line: cos²α = 0
+            let cos_2sigma_m = if cos_squared_alpha != 0. {
+                cos_sigma - 2. * sin_u1 * sin_u2 / cos_squared_alpha
+            } else {
+                0.
+            };
+            let c = c_coefficient(cos_squared_alpha);
+            let previous = lambda;
+            lambda = l + lambda_correction(c, sin_alpha, sigma, sin_sigma, cos_sigma, cos_2sigma_m);
+            if lambda.abs() > PI {
+                return Err(GeodesicError::NoConvergence);
+            }
+            if (lambda - previous).abs() < TOLERANCE {
+                let (a, b) = series_coefficients(cos_squared_alpha);
+                let (sin_lambda, cos_lambda) = lambda.sin_cos();
+                return Ok(Geodesic {
+                    distance: POLAR_RADIUS * a * (sigma - delta_sigma(b, sin_sigma, cos_sigma, cos_2sigma_m)),
+                    initial_bearing: Radians((cos_u2 * sin_lambda).atan2(cos_u1 * sin_u2 - sin_u1 * cos_u2 * cos_lambda)).wrap_two_pi(),
+                    final_bearing: Radians((cos_u1 * sin_lambda).atan2(-sin_u1 * cos_u2 + cos_u1 * sin_u2 * cos_lambda)).wrap_two_pi(),
+                });
+            }
+        }
+        Err(GeodesicError::NoConvergence)
+    }
+
+    /// Geodesic distance in meters along the WGS-84 ellipsoid, altitudes are ignored
+    pub fn distance_to(&self, other: &LLA) -> Result<f64, GeodesicError> {
+        Ok(self.geodesic_to(other)?.distance)
+    }
+
+    /// Azimuth of the geodesic towards `other`, clockwise from north in `[0, 2π)`
+    pub fn initial_bearing_to(&self, other: &LLA) -> Result<Radians, GeodesicError> {
+        Ok(self.geodesic_to(other)?.initial_bearing)
+    }
+
+    /// Vincenty's direct solution: the point `distance` meters away along the geodesic leaving at `bearing`.
+    /// Returns the destination (at this altitude) and the final bearing there.
+    pub fn destination_with_bearing(&self, bearing: impl Into<Radians>, distance: f64) -> (LLA, Radians) {
+        let (sin_alpha1, cos_alpha1) = bearing.into().0.sin_cos();
+        let (sin_u1, cos_u1) = reduced_latitude(self.lat().0);
+        let sigma1 = (sin_u1 / cos_u1).atan2(cos_alpha1);
+        let sin_alpha = cos_u1 * sin_alpha1;
+        let cos_squared_alpha = 1. - sin_alpha * sin_alpha;
+        let (a, b) = series_coefficients(cos_squared_alpha);
+
+        let sigma0 = distance / (POLAR_RADIUS * a);
+        let mut sigma = sigma0;
+        let (mut sin_sigma, mut cos_sigma, mut cos_2sigma_m);
+        let mut iterations = 0;
+        loop {
+            cos_2sigma_m = (2. * sigma1 + sigma).cos();
+            (sin_sigma, cos_sigma) = sigma.sin_cos();
+            let previous = sigma;
+            sigma = sigma0 + delta_sigma(b, sin_sigma, cos_sigma, cos_2sigma_m);
+            iterations += 1;
+            //The direct iteration always converges, the bound only guards against NaN input
+            if (sigma - previous).abs() < TOLERANCE || iterations >= MAX_ITERATIONS {
+                break;
+            }
+        }
+        (sin_sigma, cos_sigma) = sigma.sin_cos();
+        cos_2sigma_m = (2. * sigma1 + sigma).cos();
+
+        let x = sin_u1 * sin_sigma - cos_u1 * cos_sigma * cos_alpha1;
+        let latitude = (sin_u1 * cos_sigma + cos_u1 * sin_sigma * cos_alpha1).atan2((1. - Earth::FLATNESS) * (sin_alpha * sin_alpha + x * x).sqrt());
+        let lambda = (sin_sigma * sin_alpha1).atan2(cos_u1 * cos_sigma - sin_u1 * sin_sigma * cos_alpha1);
+        let c = c_coefficient(cos_squared_alpha);
+        let l = lambda - lambda_correction(c, sin_alpha, sigma, sin_sigma, cos_sigma, cos_2sigma_m);
+
+        (
+            LLA::from_rads(Radians(latitude), Radians(self.lon().0 + l).wrap_pi(), self.altitude),
+            Radians(sin_alpha.atan2(-x)).wrap_two_pi(),
+        )
+    }
+
+    /// Point `distance` meters away along the geodesic leaving at `bearing`, at this altitude
+    pub fn destination(&self, bearing: impl Into<Radians>, distance: f64) -> LLA {
+        self.destination_with_bearing(bearing, distance).0
+    }
+
+    /// Point at `fraction` (0 at self, 1 at `other`) along the geodesic, altitude interpolated linearly
+    pub fn intermediate_point(&self, other: &LLA, fraction: f64) -> Result<LLA, GeodesicError> {
+        let geodesic = self.geodesic_to(other)?;
+        let mut point = self.destination(geodesic.initial_bearing, geodesic.distance * fraction);
+        point.altitude = self.altitude + (other.altitude - self.altitude) * fraction as f32;
+        Ok(point)
+    }
+
+    /// `count` points evenly spaced along the geodesic, both ends included
+    pub fn intermediate_points(&self, other: &LLA, count: usize) -> Result<Vec<LLA>, GeodesicError> {
+        let geodesic = self.geodesic_to(other)?;
+        let last = count.saturating_sub(1).max(1) as f64;
+        Ok((0..count)
+            .map(|i| {
+                let fraction = i as f64 / last;
+                let mut point = self.destination(geodesic.initial_bearing, geodesic.distance * fraction);
+                point.altitude = self.altitude + (other.altitude - self.altitude) * fraction as f32;
+                point
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::excessive_precision)]
+mod tests {
+    use super::*;
+    use crate::lla::lla;
+
+    /// GeographicLib test cases (python/test_geodesic.py, drawn from GeodTest.dat):
+    /// lat1, lon1, azi1, lat2, lon2, azi2 (degrees), s12 (meters)
+    const GEOD_TEST: [(f64, f64, f64, f64, f64, f64, f64); 20] = [
+        (35.60777, -139.44815, 111.098748429560326, -11.17491, -69.95921, 129.289270889708762, 8935244.5604818305),
+        (55.52454, 106.05087, 22.020059880982801, 77.03196, 197.18234, 109.112041110671519, 4105086.1713924406),
+        (-21.97856, 142.59065, -32.44456876433189, 41.84138, 98.56635, -41.84359951440466, 8394328.894657671),
+        (-66.99028, 112.2363, 173.73491240878403, -12.70631, 285.90344, 2.512956620913668, 11150344.2312080241),
+        (-17.42761, 173.34268, -159.033557661192928, -15.84784, 5.93557, -20.787484651536988, 16076603.1631180673),
+        (32.84994, 48.28919, 150.492927788121982, -56.28556, 202.29132, 48.113449399816759, 16727068.9438164461),
+        (6.96833, 52.74123, 92.581585386317712, -7.39675, 206.17291, 90.721692165923907, 17102477.2496958388),
+        (-50.56724, -16.30485, -105.439679907590164, -33.56571, -94.97412, -47.348547835650331, 6455670.5118668696),
+        (-58.93002, -8.90775, 140.965397902500679, -8.91104, 133.13503, 19.255429433416599, 11756066.0219864627),
+        (-68.82867, -74.28391, 93.774347763114881, -50.63005, -8.36685, 34.65564085411343, 3956936.926063544),
+        (-10.62672, -32.0898, -86.426713286747751, 5.883, -134.31681, -80.473780971034875, 11470869.3864563009),
+        (-21.76221, 166.90563, 29.319421206936428, 48.72884, 213.97627, 43.508671946410168, 9098627.3986554915),
+        (-19.79938, -174.47484, 71.167275780171533, -11.99349, -154.35109, 65.589099775199228, 2319004.8601169389),
+        (-11.95887, -116.94513, 92.712619830452549, 4.57352, 7.16501, 78.64960934409585, 13834722.5801401374),
+        (-87.85331, 85.66836, -65.120313040242748, 66.48646, 16.09921, -4.888658719272296, 17286615.3147144645),
+        (1.74708, 128.32011, -101.584843631173858, -11.16617, 11.87109, -86.325793296437476, 12942901.1241347408),
+        (-25.72959, -144.90758, -153.647468693117198, -57.70581, -269.17879, -48.343983158876487, 9413446.7452453107),
+        (-41.22777, 122.32875, 14.285113402275739, -7.57291, 130.37946, 10.805303085187369, 3812686.035106021),
+        (11.01307, 138.25278, 79.43682622782374, 6.62726, 247.05981, 103.708090215522657, 11911190.819018408),
+        (-29.47124, 95.14681, -163.779130441688382, -27.46601, -69.15955, -15.909335945554969, 13487015.8381145492),
+    ];
+
+    /// Difference between two azimuths in degrees, wrapped to ±180
+    fn azimuth_error(actual: Radians, expected: f64) -> f64 {
+        (actual.to_degrees() - Degrees(expected)).wrap_180().0.abs()
+    }
+
+    #[test]
+    fn inverse_matches_geod_test() {
+        for (lat1, lon1, azi1, lat2, lon2, azi2, s12) in GEOD_TEST {
+            let geodesic = lla(lat1, lon1, 0.).geodesic_to(&lla(lat2, lon2, 0.)).unwrap();
+            assert!((geodesic.distance - s12).abs() < 1e-3, "{} m instead of {} m", geodesic.distance, s12);
+            assert!(azimuth_error(geodesic.initial_bearing, azi1) < 1e-8);
+            assert!(azimuth_error(geodesic.final_bearing, azi2) < 1e-8);
+        }
+    }
+
+    #[test]
+    fn direct_matches_geod_test() {
+        for (lat1, lon1, azi1, lat2, lon2, azi2, s12) in GEOD_TEST {
+            let (destination, final_bearing) = lla(lat1, lon1, 0.).destination_with_bearing(Degrees(azi1), s12);
+            assert!((destination.lat().to_degrees().0 - lat2).abs() < 1e-8);
+            assert!((destination.lon().to_degrees() - Degrees(lon2)).wrap_180().0.abs() < 1e-8);
+            assert!(azimuth_error(final_bearing, azi2) < 1e-8);
+        }
+    }
+
+    #[test]
+    fn near_antipodal_inverse() {
+        //GeodSolve6, 10 and 11: nearly antipodal, but far enough from the equator for Vincenty to converge
+        let cases = [
+            (88.202499451857, -88.202499451857, 179.981022032992859592, 20003898.214),
+            (89.333123580033, -89.333123580032997687, 179.99295812360148422, 20003926.881),
+            (52.784459512564, -52.784459512563990912, 179.634407464943777557, 19991596.095),
+            (48.522876735459, -48.52287673545898293, 179.599720456223079643, 19989144.774),
+        ];
+        for (lat1, lat2, lon2, s12) in cases {
+            let distance = lla(lat1, 0., 0.).distance_to(&lla(lat2, lon2, 0.)).unwrap();
+            assert!((distance - s12).abs() < 1e-3, "{} m instead of {} m", distance, s12);
+        }
+    }
+
+    #[test]
+    fn antipodal_inverse_does_not_converge() {
+        //GeodSolve9 (19993558.287 m) is reported as an error rather than a wrong distance
+        let error = Err(GeodesicError::NoConvergence);
+        assert_eq!(lla(56.320923501171, 0., 0.).geodesic_to(&lla(-56.320923501171, 179.664747671772880215, 0.)), error);
+        assert_eq!(lla(0., 0., 0.).geodesic_to(&lla(0.5, 179.7, 0.)), error);
+        assert_eq!(lla(0., 0., 0.).geodesic_to(&lla(0., 179.9, 0.)), error);
+    }
+
+    #[test]
+    fn coincident_points() {
+        let geodesic = lla(45., -73., 0.).geodesic_to(&lla(45., -73., 0.)).unwrap();
+        assert_eq!(geodesic.distance, 0.);
+    }
+}
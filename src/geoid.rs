@@ -0,0 +1,236 @@
+use std::path::Path;
+
+use crate::prelude::*;
+
+#[derive(thiserror::Error, Debug)]
+pub enum GeoidError {
+    #[error("IO Error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Invalid geoid grid: {0}")]
+    Format(String),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Interpolation {
+    #[default]
+    Bilinear,
+    Bicubic,
+}
+
+/// Geoid undulation grid (EGM96, EGM2008...) in the GeographicLib PGM format: a 16-bit binary PGM whose
+/// header comments carry `Offset` and `Scale`, with rows running from 90°N to 90°S and columns from 0°E eastwards.
+/// See <https://geographiclib.sourceforge.io/C++/doc/geoid.html>
+pub struct Geoid {
+    width: usize,
+    height: usize,
+    offset: f64,
+    scale: f64,
+    data: Vec<u16>,
+    interpolation: Interpolation,
+}
+
+/// Next whitespace-separated header token, collecting `# Key Value` comments on the way
+fn next_token<'a>(bytes: &'a [u8], pos: &mut usize, comments: &mut Vec<&'a [u8]>) -> Option<&'a [u8]> {
+    loop {
+        while *pos < bytes.len() && bytes[*pos].is_ascii_whitespace() {
+            *pos += 1;
+        }
+        if *pos < bytes.len() && bytes[*pos] == b'#' {
+            let start = *pos;
+            while *pos < bytes.len() && bytes[*pos] != b'\n' {
+                *pos += 1;
+            }
+            comments.push(&bytes[start..*pos]);
+            continue;
+        }
+        let start = *pos;
+        while *pos < bytes.len() && !bytes[*pos].is_ascii_whitespace() {
+            *pos += 1;
+        }
+        return (start < *pos).then(|| &bytes[start..*pos]);
+    }
+}
+
+fn parse_token<T: std::str::FromStr>(token: Option<&[u8]>, what: &str) -> Result<T, GeoidError> {
+    token
+        .and_then(|t| std::str::from_utf8(t).ok())
+        .and_then(|t| t.parse().ok())
+        .ok_or_else(|| GeoidError::Format(format!("bad {}", what)))
+}
+
+fn comment_value(comments: &[&[u8]], key: &str) -> Option<f64> {
+    comments.iter().find_map(|c| {
+        let mut words = std::str::from_utf8(c).ok()?.trim_start_matches('#').split_whitespace();
+        (words.next()? == key).then_some(())?;
+        words.next()?.parse().ok()
+    })
+}
+
+impl Geoid {
+    pub fn load(path: impl AsRef<Path>, interpolation: Interpolation) -> Result<Self, GeoidError> {
+        Self::from_pgm(&std::fs::read(path)?, interpolation)
+    }
+
+    pub fn from_pgm(bytes: &[u8], interpolation: Interpolation) -> Result<Self, GeoidError> {
+        let mut pos = 0;
+        let mut comments = Vec::new();
+        if next_token(bytes, &mut pos, &mut comments) != Some(b"P5") {
+            return Err(GeoidError::Format("not a binary PGM (P5) file".to_owned()));
+        }
+        let width: usize = parse_token(next_token(bytes, &mut pos, &mut comments), "width")?;
+        let height: usize = parse_token(next_token(bytes, &mut pos, &mut comments), "height")?;
+        let max_value: u32 = parse_token(next_token(bytes, &mut pos, &mut comments), "max value")?;
+        if max_value != 65535 {
+            return Err(GeoidError::Format("expected 16-bit samples".to_owned()));
+        }
+        if width == 0 || height < 2 || width != 2 * (height - 1) {
+            return Err(GeoidError::Format(format!("unexpected grid size {}x{}", width, height)));
+        }
+        let offset = comment_value(&comments, "Offset").ok_or_else(|| GeoidError::Format("missing Offset".to_owned()))?;
+        let scale = comment_value(&comments, "Scale").ok_or_else(|| GeoidError::Format("missing Scale".to_owned()))?;
+
+        //Exactly one whitespace byte separates the header from the samples
+        let samples = &bytes[(pos + 1).min(bytes.len())..];
+        if samples.len() < width * height * 2 {
+            return Err(GeoidError::Format("truncated sample data".to_owned()));
+        }
+        let data = samples
+            .chunks_exact(2)
+            .take(width * height)
+            .map(|b| u16::from_be_bytes([b[0], b[1]]))
+            .collect();
+
+        Ok(Self {
+            width,
+            height,
+            offset,
+            scale,
+            data,
+            interpolation,
+        })
+    }
+
+    /// Grid value in meters, column wrapped in longitude and row clamped at the poles
+    fn sample(&self, row: isize, col: isize) -> f64 {
+        let row = row.clamp(0, self.height as isize - 1) as usize;
+        let col = col.rem_euclid(self.width as isize) as usize;
+        self.offset + self.scale * self.data[row * self.width + col] as f64
+    }
+
+    /// Geoid height above the WGS-84 ellipsoid (meters)
+    pub fn undulation(&self, lla: &LLA) -> f32 {
+        let spacing = 360. / self.width as f64;
+        let x = lla.lon().to_degrees().wrap_360().0 / spacing;
+        let y = (90. - lla.lat().to_degrees().0) / spacing;
+        let (col, row) = (x.floor() as isize, y.floor() as isize);
+        let (fx, fy) = (x - x.floor(), y - y.floor());
+
+        match self.interpolation {
+            Interpolation::Bilinear => {
+                let top = self.sample(row, col) * (1. - fx) + self.sample(row, col + 1) * fx;
+                let bottom = self.sample(row + 1, col) * (1. - fx) + self.sample(row + 1, col + 1) * fx;
+                (top * (1. - fy) + bottom * fy) as f32
+            }
+            Interpolation::Bicubic => {
+                let rows: [f64; 4] = core::array::from_fn(|i| {
+                    let r = row - 1 + i as isize;
+                    catmull_rom(
+                        [
+                            self.sample(r, col - 1),
+                            self.sample(r, col),
+                            self.sample(r, col + 1),
+                            self.sample(r, col + 2),
+                        ],
+                        fx,
+                    )
+                });
+                catmull_rom(rows, fy) as f32
+            }
+        }
+    }
+}
+
+/// Cubic convolution between p[1] and p[2]
+fn catmull_rom(p: [f64; 4], t: f64) -> f64 {
+    p[1] + 0.5 * t * (p[2] - p[0] + t * (2. * p[0] - 5. * p[1] + 4. * p[2] - p[3] + t * (3. * (p[1] - p[2]) + p[3] - p[0])))
+}
+
+impl LLA {
+    /// Height above the geoid (MSL), from the ellipsoidal `altitude`
+    pub fn orthometric_height(&self, geoid: &Geoid) -> f32 {
+        self.altitude - geoid.undulation(self)
+    }
+
+    /// Builds an `LLA` from a height above the geoid (MSL)
+    pub fn from_orthometric(latitude: impl Into<Radians>, longitude: impl Into<Radians>, height: f32, geoid: &Geoid) -> Result<Self, LLAError> {
+        let mut lla = LLA::new(latitude, longitude, height)?;
+        lla.altitude = height + geoid.undulation(&lla);
+        Ok(lla)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lla::lla;
+
+    /// 45° grid holding `10 row + col` meters
+    fn linear_grid(interpolation: Interpolation) -> Geoid {
+        let (width, height) = (8, 5);
+        let mut pgm = format!("P5\n# Offset -100\n# Scale 0.01\n{} {}\n65535\n", width, height).into_bytes();
+        for row in 0..height {
+            for col in 0..width {
+                let value = 10. * row as f64 + col as f64;
+                pgm.extend_from_slice(&(((value + 100.) / 0.01).round() as u16).to_be_bytes());
+            }
+        }
+        Geoid::from_pgm(&pgm, interpolation).unwrap()
+    }
+
+    #[test]
+    fn interpolations_reproduce_a_linear_grid() {
+        for interpolation in [Interpolation::Bilinear, Interpolation::Bicubic] {
+            let undulation = linear_grid(interpolation).undulation(&lla(22.5, 112.5, 0.));
+            assert!((undulation - 17.5).abs() < 1e-4, "{:?}: {}", interpolation, undulation);
+        }
+    }
+
+    #[test]
+    fn bilinear_wraps_in_longitude() {
+        //Halfway between column 7 (315°E) and column 0, on row 2
+        let undulation = linear_grid(Interpolation::Bilinear).undulation(&lla(0., -22.5, 0.));
+        assert!((undulation - 23.5).abs() < 1e-4, "{}", undulation);
+    }
+
+    #[test]
+    fn rejects_bad_headers() {
+        assert!(matches!(Geoid::from_pgm(b"P2\n8 5\n65535\n", Interpolation::Bilinear), Err(GeoidError::Format(_))));
+        assert!(matches!(Geoid::from_pgm(b"P5\n# Offset -100\n8 5\n65535\n", Interpolation::Bilinear), Err(GeoidError::Format(_))));
+        assert!(matches!(Geoid::from_pgm(b"P5\n# Offset -100\n# Scale 0.01\n8 5\n65535\n\0\0", Interpolation::Bilinear), Err(GeoidError::Format(_))));
+    }
+
+    /// NGA EGM96 test points (OUTINTPT.DAT): latitude, longitude (degrees), undulation (meters).
+    /// Needs the GeographicLib `egm96-5.pgm` grid in `data/` (see `data/README.md`) or at `EGM96_PGM`
+    #[test]
+    #[ignore = "needs data/egm96-5.pgm, which is not vendored yet"]
+    fn egm96_test_points() {
+        let path = std::env::var("EGM96_PGM").unwrap_or_else(|_| concat!(env!("CARGO_MANIFEST_DIR"), "/data/egm96-5.pgm").to_owned());
+        let points = [
+            (38.628155, 269.779155, -31.628),
+            (-14.621217, 305.021114, -2.969),
+            (46.874319, 102.448729, -43.575),
+            (-23.617446, 133.874712, 15.871),
+            (38.625473, 359.9995, 50.066),
+            (-0.466744, 0.0023, 17.329),
+        ];
+        for interpolation in [Interpolation::Bilinear, Interpolation::Bicubic] {
+            let geoid = Geoid::load(&path, interpolation).unwrap();
+            for (latitude, longitude, expected) in points {
+                let undulation = geoid.undulation(&lla(latitude, longitude, 0.)) as f64;
+                //Interpolating the 5' grid rather than summing the harmonics costs up to about a decimeter
+                assert!((undulation - expected).abs() < 0.15, "{:?} at {}, {}: {} m instead of {} m", interpolation, latitude, longitude, undulation, expected);
+            }
+        }
+    }
+}
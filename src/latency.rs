@@ -0,0 +1,118 @@
+//! Measurement latency of the landing zone: each update describes the target as it was some time before it
+//! is sent.
+//!
+//! With jitter, an update can be older than the one before it, so measurement timestamps are not monotonic
+//! (unless extrapolated to the send time).
+
+use rand::Rng;
+
+use crate::noise::gaussian;
+
+#[derive(thiserror::Error, Debug, Clone, Copy, PartialEq)]
+pub enum LatencyError {
+    #[error("Latency must not be negative, got {0} s")]
+    NegativeFixed(f64),
+    #[error("Latency jitter must not be negative, got {0} s")]
+    NegativeJitter(f64),
+}
+
+/// Distribution of the latency jitter
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum JitterDistribution {
+    /// Uniform between 0 and the jitter
+    Uniform,
+    /// Zero mean, the jitter being its 1-sigma
+    Gaussian,
+    /// The jitter being its mean, like queueing delays
+    Exponential,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Latency {
+    /// seconds
+    fixed: f64,
+    /// seconds
+    jitter: f64,
+    distribution: JitterDistribution,
+}
+
+impl Latency {
+    pub fn new(fixed: f64, jitter: f64, distribution: JitterDistribution) -> Result<Self, LatencyError> {
+        if fixed < 0. || fixed.is_nan() {
+            return Err(LatencyError::NegativeFixed(fixed));
+        }
+        if jitter < 0. || jitter.is_nan() {
+            return Err(LatencyError::NegativeJitter(jitter));
+        }
+        Ok(Self { fixed, jitter, distribution })
+    }
+
+    /// Age of a measurement (seconds), clamped at zero when a Gaussian jitter would make it negative
+    pub fn sample(&self, rng: &mut impl Rng) -> f64 {
+        let jitter = match self.distribution {
+            JitterDistribution::Uniform => self.jitter * rng.random::<f64>(),
+            JitterDistribution::Gaussian => self.jitter * gaussian(rng),
+            JitterDistribution::Exponential => -self.jitter * (1. - rng.random::<f64>()).ln(),
+        };
+        (self.fixed + jitter).max(0.)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    fn samples(fixed: f64, jitter: f64, distribution: JitterDistribution) -> Vec<f64> {
+        let latency = Latency::new(fixed, jitter, distribution).unwrap();
+        let mut rng = ChaCha8Rng::seed_from_u64(11);
+        (0..20_000).map(|_| latency.sample(&mut rng)).collect()
+    }
+
+    fn mean(samples: &[f64]) -> f64 {
+        samples.iter().sum::<f64>() / samples.len() as f64
+    }
+
+    #[test]
+    fn rejects_negative_values() {
+        assert_eq!(Latency::new(-0.1, 0., JitterDistribution::Uniform), Err(LatencyError::NegativeFixed(-0.1)));
+        assert_eq!(Latency::new(0.1, -0.2, JitterDistribution::Gaussian), Err(LatencyError::NegativeJitter(-0.2)));
+        assert!(Latency::new(f64::NAN, 0., JitterDistribution::Uniform).is_err());
+    }
+
+    #[test]
+    fn fixed_without_jitter() {
+        for distribution in [JitterDistribution::Uniform, JitterDistribution::Gaussian, JitterDistribution::Exponential] {
+            assert!(samples(0.2, 0., distribution).iter().all(|&s| s == 0.2));
+        }
+    }
+
+    #[test]
+    fn uniform_jitter() {
+        let samples = samples(0.1, 0.05, JitterDistribution::Uniform);
+        assert!(samples.iter().all(|&s| (0.1..0.15).contains(&s)));
+        assert!((mean(&samples) - 0.125).abs() < 1e-3);
+    }
+
+    #[test]
+    fn gaussian_jitter_is_clamped_at_zero() {
+        //Half of a zero-mean jitter would be negative
+        let clamped = samples(0., 0.1, JitterDistribution::Gaussian);
+        assert!(clamped.iter().all(|&s| s >= 0.));
+        let zeros = clamped.iter().filter(|&&s| s == 0.).count() as f64 / clamped.len() as f64;
+        assert!((zeros - 0.5).abs() < 0.02, "{}", zeros);
+        //Mean of the positive half of a normal distribution, σ √(2/π) / 2
+        assert!((mean(&clamped) - 0.1 * (2. / core::f64::consts::PI).sqrt() / 2.).abs() < 2e-3);
+        //Far from zero it is symmetric around the fixed part
+        assert!((mean(&samples(1., 0.1, JitterDistribution::Gaussian)) - 1.).abs() < 3e-3);
+    }
+
+    #[test]
+    fn exponential_jitter() {
+        let samples = samples(0.05, 0.2, JitterDistribution::Exponential);
+        assert!(samples.iter().all(|&s| s >= 0.05));
+        assert!((mean(&samples) - 0.25).abs() < 6e-3);
+    }
+}
@@ -0,0 +1,31 @@
+//! Coordinate conversions, target motion and GNSS fault models behind the `landy` landing zone simulator.
+
+pub mod angle;
+pub mod attitude;
+pub mod batch;
+pub mod earth;
+pub mod fault;
+pub mod format;
+pub mod ecef;
+pub mod eci;
+pub mod frame;
+pub mod geodesic;
+pub mod geoid;
+pub mod latency;
+pub mod lla;
+pub mod mgrs;
+pub mod noise;
+pub mod prelude;
+pub mod reference;
+pub mod route;
+pub mod scenario;
+pub mod scheduler;
+pub mod sea_state;
+#[cfg(feature = "serde")]
+pub mod serialization;
+pub mod skypack;
+pub mod trajectory;
+pub mod utm;
+pub mod wmm;
+
+pub use crate::prelude::*;
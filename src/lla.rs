@@ -1,36 +1,66 @@
+use core::f64::consts::{FRAC_PI_2, PI};
+
 use crate::*;
 
-#[derive(Clone, Copy, Debug)]
+#[derive(thiserror::Error, Debug, Clone, Copy, PartialEq)]
+pub enum LLAError {
+    #[error("Latitude {0} degrees is outside [-90, 90]")]
+    LatitudeOutOfRange(Degrees),
+    #[error("Coordinate is not finite")]
+    NotFinite,
+}
+
+#[derive(Clone, Copy, Debug, Default)]
 pub struct LLA {
     /// radians
-    pub latitude: f64,
+    latitude: f64,
     /// radians
-    pub longitude: f64,
+    longitude: f64,
     /// meters above WGS-84 ellipsoid
     pub altitude: f32,
 }
 
-impl Default for LLA {
-    fn default() -> Self {
-        Self {
-            latitude: Default::default(),
-            longitude: Default::default(),
-            altitude: Default::default(),
+impl LLA {
+    /// Validated constructor: latitude must lie within ±90° and longitude is wrapped to (-180°, 180°]
+    pub fn new(latitude: impl Into<Radians>, longitude: impl Into<Radians>, altitude: f32) -> Result<Self, LLAError> {
+        let latitude: Radians = latitude.into();
+        let longitude: Radians = longitude.into();
+        if !latitude.0.is_finite() || !longitude.0.is_finite() || !altitude.is_finite() {
+            return Err(LLAError::NotFinite);
         }
+        if latitude.0.abs() > FRAC_PI_2 {
+            return Err(LLAError::LatitudeOutOfRange(latitude.to_degrees()));
+        }
+        Ok(Self {
+            latitude: latitude.0,
+            //180°E rather than 180°W on the antimeridian
+            longitude: match longitude.wrap_pi().0 {
+                longitude if longitude == -PI => PI,
+                longitude => longitude,
+            },
+            altitude,
+        })
     }
-}
 
-impl LLA {
-    pub fn from_rads(latitude: f64, longitude: f64, altitude: f32) -> Self {
-        Self { latitude, longitude, altitude }
-    }
-    pub fn from_degs(latitude: f64, longitude: f64, altitude: f32) -> Self {
+    /// Unvalidated constructor, prefer `LLA::new` for external input
+    pub fn from_rads(latitude: Radians, longitude: Radians, altitude: f32) -> Self {
         Self {
-            latitude: latitude.to_radians(),
-            longitude: longitude.to_radians(),
+            latitude: latitude.0,
+            longitude: longitude.0,
             altitude,
         }
     }
+    pub fn from_degs(latitude: Degrees, longitude: Degrees, altitude: f32) -> Self {
+        Self::from_rads(latitude.to_radians(), longitude.to_radians(), altitude)
+    }
+
+    pub fn lat(&self) -> Radians {
+        Radians(self.latitude)
+    }
+
+    pub fn lon(&self) -> Radians {
+        Radians(self.longitude)
+    }
 
     pub fn to_ecef(&self) -> ECEF {
         //https://stackoverflow.com/questions/19478200/convert-latitude-and-longitude-to-ecef-coordinates-system
@@ -40,7 +70,7 @@ impl LLA {
         let cos_lon = (self.longitude).cos();
         let sin_lon = (self.longitude).sin();
 
-        let n = Earth::EQUATORIAL_RADIUS / (1. - Earth::ECCENTRICITY_SQUARED as f64 * sin_lat * sin_lat).sqrt();
+        let n = Earth::prime_vertical_radius(self.lat());
 
         ECEF::new(
             (n + self.altitude as f64) * cos_lat * cos_lon,
@@ -49,16 +79,16 @@ impl LLA {
         )
     }
 
-    pub fn to_tangent(&self, reference: &Reference) -> Vector3<f32> {
+    pub fn to_tangent(&self, reference: &Reference) -> Ned<f32> {
         reference.lla_to_tangent(*self)
     }
 
-    pub fn from_tangent(reference: &Reference, tangent: Vector3<f32>) -> LLA {
+    pub fn from_tangent(reference: &Reference, tangent: Ned<f32>) -> LLA {
         //1 - Projeter coordonnee local dans le repere de la terre
-        let local_coor_in_earth = reference.tangent_to_ecef(tangent);
+        let local_coor_in_earth = reference.ned_to_ecef_vec(tangent);
 
         //3 - Calculer les coordonnees local dans le repere ECEF Cartesien
-        let local_coor_in_cart_ecef = local_coor_in_earth + reference.ecef;
+        let local_coor_in_cart_ecef = reference.ecef + local_coor_in_earth;
 
         //4 - Convertir la position local ECEF cartesien dans ECEF LLA
         //Methode Borkowski
@@ -69,7 +99,99 @@ impl LLA {
         local_coor_in_cart_ecef.to_lla()
     }
 
+    /// Fast displacement by a small NED offset using the local radii of curvature, the offset following the
+    /// ellipsoid rather than the tangent plane. Against `Reference::tangent_to_lla` at a horizontal distance d,
+    /// the result sits d²/2R lower (4 cm at 700 m, 16 cm at 1.4 km) and east²·tan(latitude)/2R further north
+    /// as meridians converge (31 cm for 2 km east at 45°). Use `Reference` when the tangent plane matters.
+    pub fn offset(&self, offset: Ned<f32>) -> LLA {
+        let h = self.altitude as f64;
+        let latitude = self.latitude + offset.north() as f64 / (Earth::meridian_radius(self.lat()) + h);
+        let mid_latitude = Radians((self.latitude + latitude) / 2.);
+        LLA::from_rads(
+            Radians(latitude),
+            Radians(self.longitude + offset.east() as f64 / ((Earth::prime_vertical_radius(mid_latitude) + h) * mid_latitude.cos())).wrap_pi(),
+            self.altitude - offset.down(),
+        )
+    }
+
+    /// Inverse of `offset`: the small NED offset from this position to `other`
+    pub fn offset_to(&self, other: &LLA) -> Ned<f32> {
+        let h = self.altitude as f64;
+        let mid_latitude = Radians((self.latitude + other.latitude) / 2.);
+        Ned::new(
+            ((other.latitude - self.latitude) * (Earth::meridian_radius(mid_latitude) + h)) as f32,
+            ((other.lon() - self.lon()).wrap_pi().0 * (Earth::prime_vertical_radius(mid_latitude) + h) * mid_latitude.cos()) as f32,
+            self.altitude - other.altitude,
+        )
+    }
+
     pub fn as_slice_rads(&self) -> [f64; 3] {
         [self.latitude, self.longitude, self.altitude as f64]
     }
 }
+
+/// Test fixture in degrees
+#[cfg(test)]
+pub(crate) fn lla(latitude: f64, longitude: f64, altitude: f32) -> LLA {
+    LLA::from_degs(Degrees(latitude), Degrees(longitude), altitude)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_latitude_out_of_range() {
+        assert!(matches!(LLA::new(Degrees(90.000001), Degrees(0.), 0.), Err(LLAError::LatitudeOutOfRange(_))));
+        assert!(matches!(LLA::new(Degrees(-91.), Degrees(0.), 0.), Err(LLAError::LatitudeOutOfRange(_))));
+        assert!(LLA::new(Degrees(90.), Degrees(0.), 0.).is_ok());
+        assert!(LLA::new(Degrees(-90.), Degrees(0.), 0.).is_ok());
+    }
+
+    #[test]
+    fn rejects_non_finite() {
+        for (latitude, longitude, altitude) in [(f64::NAN, 0., 0.), (0., f64::NAN, 0.), (0., 0., f32::NAN), (f64::INFINITY, 0., 0.), (0., f64::NEG_INFINITY, 0.), (0., 0., f32::INFINITY)] {
+            assert_eq!(LLA::new(Radians(latitude), Radians(longitude), altitude).err(), Some(LLAError::NotFinite));
+        }
+    }
+
+    #[test]
+    fn wraps_longitude() {
+        let longitude = |degrees: f64| LLA::new(Degrees(0.), Degrees(degrees), 0.).unwrap().lon().to_degrees().0;
+        assert_eq!(longitude(180.), 180.);
+        assert_eq!(longitude(-180.), 180.);
+        assert!((longitude(540.) - 180.).abs() < 1e-12);
+        assert!((longitude(190.) + 170.).abs() < 1e-12);
+        assert!((longitude(-190.) - 170.).abs() < 1e-12);
+        assert!((longitude(360.)).abs() < 1e-12);
+        assert!((longitude(-73.5) + 73.5).abs() < 1e-12);
+        assert!(longitude(-1e-12) < 0. && longitude(-1e-12) > -1e-11);
+    }
+
+    #[test]
+    fn offset_round_trips() {
+        let origin = LLA::from_degs(Degrees(-33.86), Degrees(179.99), 120.);
+        for offset in [Ned::new(250., -400., -30.), Ned::new(-1000., 1000., 15.), Ned::new(0., 2000., 0.)] {
+            let back = origin.offset_to(&origin.offset(offset));
+            assert!((back - offset).as_vector().norm() < 1e-3, "{:?} instead of {:?}", back, offset);
+        }
+        //Across the antimeridian
+        assert!(origin.offset(Ned::new(0., 2000., 0.)).lon().0 < 0.);
+    }
+
+    #[test]
+    fn offset_drifts_from_the_tangent_plane() {
+        let origin = LLA::from_degs(Degrees(45.), Degrees(7.), 120.);
+        let reference = Reference::new(origin);
+        let drift = |north: f32, east: f32| reference.lla_to_tangent(origin.offset(Ned::new(north, east, 0.))) - Ned::new(north, east, 0.);
+        let radius = Earth::gaussian_radius(Degrees(45.));
+        for (north, east, bound) in [(500., 500., 0.05), (1000., 1000., 0.2)] {
+            let drift = drift(north, east);
+            assert!(drift.as_vector().norm() < bound, "{:?}", drift);
+            let drop = (north * north + east * east) as f64 / (2. * radius);
+            assert!((drift.down() as f64 - drop).abs() < 2e-3, "{:?} instead of {} m down", drift, drop);
+        }
+        let north = drift(0., 2000.).north() as f64;
+        assert!((north - 2000_f64.powi(2) / (2. * radius)).abs() < 0.01, "{} m north", north);
+    }
+}
@@ -1,40 +1,94 @@
-mod earth;
-mod ecef;
-mod lla;
-mod prelude;
-mod reference;
-mod skypack;
-
-pub use crate::prelude::*;
-use crate::skypack::{DeviceError, ResponsePacket, Skypack};
+use landy::prelude::*;
+use landy::fault::{Fault, FaultInjector, Update};
+use landy::geoid::Interpolation;
+use landy::latency::{JitterDistribution, Latency};
+use landy::noise::{NoiseModel, NoisePreset};
+use landy::route::Waypoint;
+use landy::scenario::{
+    AltitudeDatum, Event, LatencyConfig, NoiseConfig, ReferenceConfig, ReferenceSource, RpyUnit, Scenario,
+    SeaStateConfig, TargetConfig, TrajectoryConfig,
+};
+use landy::scheduler::Scheduler;
+use landy::sea_state::SeaState;
+use landy::skypack::Skypack;
+use landy::trajectory::Trajectory;
 use anyhow::Result;
-use chrono::Utc;
 use clap::Parser;
-use rand::Rng;
-use rand::rngs::ThreadRng;
-use serde::{Deserialize, Serialize};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
 use std::sync::Arc;
-use std::{thread::sleep, time::Duration};
+use std::time::Duration;
 use tokio::time::Instant;
 
 #[derive(Parser, Debug)]
-#[command(author, version, about)]
+#[command(author, version, about, args_conflicts_with_subcommands = true)]
 struct Args {
-    /// Horizontal noise, peak-peak (meters)
-    #[arg(long = "h-noise")]
-    #[arg(long, default_value = "0")]
-    h_noise: f32,
+    #[command(subcommand)]
+    command: Option<Command>,
 
-    /// Vertical noise amplitude, peak-peak (meters)
-    #[arg(long = "v-noise")]
-    #[arg(long, default_value = "0")]
-    v_noise: f32,
+    /// Seed of the random generators, overriding the scenario's. A random seed is used and printed otherwise.
+    #[arg(long, global = true)]
+    seed: Option<u64>,
+
+    /// Run for this long, then exit (secs)
+    #[arg(long)]
+    duration: Option<f32>,
+
+    /// Fixed tangent plane origin, `lat, lon, alt`, instead of the SKYMATE reference
+    #[arg(long)]
+    reference: Option<LLA>,
+
+    /// Position error model of a typical receiver, adjusted by the other noise options
+    #[arg(long, value_enum, default_value = "none")]
+    noise: ReceiverPreset,
+
+    /// Horizontal white Gaussian noise, 1-sigma per axis (meters).
+    /// Replaces --h-noise, a uniform peak-to-peak amplitude: a peak-to-peak p is a 1-sigma of p/√12.
+    #[arg(long = "h-sigma", conflicts_with = "cep")]
+    h_sigma: Option<f32>,
+
+    /// Horizontal white noise as a CEP (meters)
+    #[arg(long)]
+    cep: Option<f32>,
+
+    /// Vertical white Gaussian noise, 1-sigma (meters).
+    /// Replaces --v-noise, a uniform peak-to-peak amplitude: a peak-to-peak p is a 1-sigma of p/√12.
+    #[arg(long = "v-sigma")]
+    v_sigma: Option<f32>,
+
+    /// Horizontal Gauss-Markov bias, 1-sigma per axis (meters)
+    #[arg(long = "h-bias")]
+    h_bias: Option<f32>,
+
+    /// Vertical Gauss-Markov bias, 1-sigma (meters)
+    #[arg(long = "v-bias")]
+    v_bias: Option<f32>,
+
+    /// Correlation time of the bias (secs)
+    #[arg(long = "bias-time")]
+    bias_time: Option<f32>,
+
+    /// Horizontal random walk (m/√s)
+    #[arg(long = "h-random-walk")]
+    h_random_walk: Option<f32>,
+
+    /// Vertical random walk (m/√s)
+    #[arg(long = "v-random-walk")]
+    v_random_walk: Option<f32>,
+
+    /// Correlation coefficient between the north and east errors
+    #[arg(long = "ne-correlation", default_value = "0", allow_negative_numbers = true)]
+    ne_correlation: f32,
 
     /// Rate (Hz)
     #[arg(long)]
     #[arg(long, default_value = "1")]
     rate: f32,
 
+    /// Align updates on GNSS time multiples of 1/rate, read from the device clock
+    #[arg(long = "phase-lock")]
+    phase_lock: bool,
+
     /// Velocity (m/s)
     #[arg(long)]
     #[arg(long, default_value = "0")]
@@ -45,38 +99,304 @@ struct Args {
     #[arg(long, default_value = "0")]
     delay: f32,
 
+    /// Target motion
+    #[arg(long, value_enum, default_value = "straight")]
+    trajectory: TrajectoryKind,
+
+    /// Turn rate of the circle trajectory, positive to the right (degrees/s)
+    #[arg(long = "turn-rate", default_value = "3")]
+    turn_rate: f32,
+
+    /// Half length of the figure-eight trajectory (meters)
+    #[arg(long = "eight-size", default_value = "50")]
+    eight_size: f32,
+
+    /// Duration of one figure-eight (secs)
+    #[arg(long = "eight-period", default_value = "120")]
+    eight_period: f32,
+
+    /// Speed reached at the end of the ramp trajectory, starting from --vel (m/s)
+    #[arg(long = "final-vel", default_value = "0")]
+    final_vel: f32,
+
+    /// Acceleration of the ramp trajectory (m/s²)
+    #[arg(long, default_value = "0.5")]
+    accel: f32,
+
+    /// Route waypoint, `lat, lon` or `ned:north,east` (meters from the SKYMATE reference), optionally followed by
+    /// `@speed` (m/s) for the leg towards it, --vel otherwise. Repeat for each waypoint.
+    #[arg(long)]
+    waypoint: Vec<Waypoint>,
+
+    /// Maximum turn rate between route legs (degrees/s)
+    #[arg(long = "max-turn-rate", default_value = "10")]
+    max_turn_rate: f32,
+
+    /// Go back to the first waypoint after the last one, forever
+    #[arg(long = "loop-route")]
+    loop_route: bool,
+
+    /// Stop the target after following the trajectory for this long (secs)
+    #[arg(long = "stop-after")]
+    stop_after: Option<f32>,
+
+    /// Deck motion of the target
+    #[arg(long = "sea-state", value_enum, default_value = "calm")]
+    sea_state: SeaStateKind,
+
+    /// Heave amplitude of the sinusoidal sea state (meters)
+    #[arg(long = "heave-amplitude", default_value = "0.5")]
+    heave_amplitude: f32,
+
+    /// Heave period of the sinusoidal sea state (secs)
+    #[arg(long = "heave-period", default_value = "8")]
+    heave_period: f32,
+
+    /// Roll amplitude of the sinusoidal sea state (degrees)
+    #[arg(long = "roll-amplitude", default_value = "3")]
+    roll_amplitude: f32,
+
+    /// Roll period of the sinusoidal sea state (secs)
+    #[arg(long = "roll-period", default_value = "10")]
+    roll_period: f32,
+
+    /// Pitch amplitude of the sinusoidal sea state (degrees)
+    #[arg(long = "pitch-amplitude", default_value = "1.5")]
+    pitch_amplitude: f32,
+
+    /// Pitch period of the sinusoidal sea state (secs)
+    #[arg(long = "pitch-period", default_value = "7")]
+    pitch_period: f32,
+
+    /// Significant wave height of the JONSWAP sea state (meters)
+    #[arg(long = "wave-height", default_value = "1")]
+    wave_height: f32,
+
+    /// Peak period of the JONSWAP sea state (secs)
+    #[arg(long = "wave-period", default_value = "8")]
+    wave_period: f32,
+
+    /// Direction the JONSWAP waves come from, relative to the bow (degrees)
+    #[arg(long = "wave-direction", default_value = "45")]
+    wave_direction: f32,
+
+    /// Fault to inject, `kind[:value]@t=<secs>` once at that time after the start or `kind[:value]@p=<probability>`
+    /// at each update. Kinds: skip:<n>, drop, jump:<meters>, outlier:<meters>, freeze:<n>, nan, timestamp:<secs>.
+    /// Repeat for each fault.
+    #[arg(long)]
+    fault: Vec<Fault>,
+
+    /// Age of the landing zone measurements when sent (secs)
+    #[arg(long, default_value = "0")]
+    latency: f32,
+
+    /// Random part of the latency (secs), see --jitter-distribution.
+    /// Successive measurement timestamps can then go backwards, unless --extrapolate is set.
+    #[arg(long, default_value = "0")]
+    jitter: f32,
+
+    /// Distribution of the latency jitter
+    #[arg(long = "jitter-distribution", value_enum, default_value = "uniform")]
+    jitter_distribution: LatencyJitter,
+
+    /// Extrapolate delayed measurements to the current time with their velocity, and stamp them with it
+    #[arg(long)]
+    extrapolate: bool,
+
+    //See `RpyUnit` for why the unit is a choice
+    /// Send the deck attitude in this unit, `[0, 0, 0]` is sent when absent
+    #[arg(long = "rpy-unit", value_enum)]
+    rpy_unit: Option<DeckRpyUnit>,
+
     /// Velocity direction in degrees
     #[arg(long = "vel-degrees")]
     #[arg(long, default_value = "0")]
     vel_degrees: f32,
 
-    /// IP address
+    /// --vel-degrees is a magnetic heading, converted to true with the World Magnetic Model (requires --wmm)
+    #[arg(long = "vel-magnetic", requires = "wmm")]
+    vel_magnetic: bool,
+
+    /// World Magnetic Model coefficient file (WMM.COF)
     #[arg(long)]
+    wmm: Option<std::path::PathBuf>,
+
+    /// IP address
+    #[arg(long, global = true)]
     #[arg(long, default_value = "127.0.0.1")]
     ip: String,
 
     /// Port
-    #[arg(long)]
+    #[arg(long, global = true)]
     #[arg(long, default_value_t = 41263)]
     port: u16,
+
+    /// Target start latitude (degrees), defaults to the reference position
+    #[arg(long, requires = "lon", allow_negative_numbers = true)]
+    lat: Option<f64>,
+
+    /// Target start longitude (degrees)
+    #[arg(long, requires = "lat", allow_negative_numbers = true)]
+    lon: Option<f64>,
+
+    /// Target start offset north of the reference (meters)
+    #[arg(long, conflicts_with_all = ["lat", "lon"], allow_negative_numbers = true)]
+    north: Option<f32>,
+
+    /// Target start offset east of the reference (meters)
+    #[arg(long, conflicts_with_all = ["lat", "lon"], allow_negative_numbers = true)]
+    east: Option<f32>,
+
+    /// Target altitude (meters), defaults to the SKYMATE reference altitude
+    #[arg(long)]
+    alt: Option<f32>,
+
+    /// Datum of --alt
+    #[arg(long = "alt-datum", value_enum, default_value = "ellipsoid")]
+    alt_datum: AltDatum,
+
+    /// Geoid grid in GeographicLib PGM format (e.g. egm96-5.pgm), required for MSL altitudes
+    #[arg(long)]
+    geoid: Option<std::path::PathBuf>,
+
+    /// Geoid grid interpolation
+    #[arg(long = "geoid-interpolation", value_enum, default_value = "bilinear")]
+    geoid_interpolation: GeoidInterpolation,
+
+    /// Horizontal distance from the tangent plane origin after which it is moved under the target (meters).
+    /// The origin never moves when unset.
+    #[arg(long = "reanchor-distance")]
+    reanchor_distance: Option<f32>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+enum TrajectoryKind {
+    /// Fixed position
+    Stationary,
+    /// Constant velocity along --vel-degrees at --vel
+    Straight,
+    /// Constant speed and --turn-rate
+    Circle,
+    /// Figure-eight along --vel-degrees of --eight-size and --eight-period
+    FigureEight,
+    /// From --vel to --final-vel at --accel along --vel-degrees
+    Ramp,
+    /// Through each --waypoint, starting at the first one
+    Route,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+enum SeaStateKind {
+    /// No deck motion
+    Calm,
+    /// Heave, roll and pitch sinusoids
+    Sinusoids,
+    /// Waves synthesised from a JONSWAP spectrum
+    Jonswap,
+}
+
+/// Command line names of `geoid::Interpolation`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+enum GeoidInterpolation {
+    Bilinear,
+    Bicubic,
+}
+
+impl From<GeoidInterpolation> for Interpolation {
+    fn from(interpolation: GeoidInterpolation) -> Self {
+        match interpolation {
+            GeoidInterpolation::Bilinear => Interpolation::Bilinear,
+            GeoidInterpolation::Bicubic => Interpolation::Bicubic,
+        }
+    }
+}
+
+/// Command line names of `noise::NoisePreset`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+enum ReceiverPreset {
+    /// No error
+    None,
+    /// Single frequency standalone GPS
+    Gps,
+    /// GPS with SBAS corrections
+    Sbas,
+    /// RTK, float ambiguities
+    RtkFloat,
+    /// RTK, fixed ambiguities
+    RtkFixed,
+}
+
+impl From<ReceiverPreset> for NoisePreset {
+    fn from(preset: ReceiverPreset) -> Self {
+        match preset {
+            ReceiverPreset::None => NoisePreset::None,
+            ReceiverPreset::Gps => NoisePreset::Gps,
+            ReceiverPreset::Sbas => NoisePreset::Sbas,
+            ReceiverPreset::RtkFloat => NoisePreset::RtkFloat,
+            ReceiverPreset::RtkFixed => NoisePreset::RtkFixed,
+        }
+    }
 }
 
-fn get_nav_lla(telemetry: &serde_json::Value) -> Result<LLA> {
-    let latitude = telemetry["nav"]["lla"][0]
-        .as_f64()
-        .ok_or_else(|| anyhow::anyhow!("Telemetry data has no latitude"))?;
-    let longitude = telemetry["nav"]["lla"][1]
-        .as_f64()
-        .ok_or_else(|| anyhow::anyhow!("Telemetry data has no latitude"))?;
-    let altitude = telemetry["nav"]["lla"][2]
-        .as_f64()
-        .ok_or_else(|| anyhow::anyhow!("Telemetry data has no latitude"))?
-        as f32;
-    Ok(LLA::from_degs(
-        latitude.to_degrees(),
-        longitude.to_degrees(),
-        altitude,
-    ))
+/// Command line names of `latency::JitterDistribution`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+enum LatencyJitter {
+    /// Uniform between 0 and the jitter
+    Uniform,
+    /// Zero mean, the jitter being its 1-sigma
+    Gaussian,
+    /// The jitter being its mean, like queueing delays
+    Exponential,
+}
+
+impl From<LatencyJitter> for JitterDistribution {
+    fn from(distribution: LatencyJitter) -> Self {
+        match distribution {
+            LatencyJitter::Uniform => JitterDistribution::Uniform,
+            LatencyJitter::Gaussian => JitterDistribution::Gaussian,
+            LatencyJitter::Exponential => JitterDistribution::Exponential,
+        }
+    }
+}
+
+/// Command line names of `scenario::AltitudeDatum`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+enum AltDatum {
+    /// Height above the WGS-84 ellipsoid
+    Ellipsoid,
+    /// Height above mean sea level (geoid)
+    Msl,
+}
+
+impl From<AltDatum> for AltitudeDatum {
+    fn from(datum: AltDatum) -> Self {
+        match datum {
+            AltDatum::Ellipsoid => AltitudeDatum::Ellipsoid,
+            AltDatum::Msl => AltitudeDatum::Msl,
+        }
+    }
+}
+
+/// Command line names of `scenario::RpyUnit`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+enum DeckRpyUnit {
+    Degrees,
+    Radians,
+}
+
+impl From<DeckRpyUnit> for RpyUnit {
+    fn from(unit: DeckRpyUnit) -> Self {
+        match unit {
+            DeckRpyUnit::Degrees => RpyUnit::Degrees,
+            DeckRpyUnit::Radians => RpyUnit::Radians,
+        }
+    }
+}
+
+#[derive(clap::Subcommand, Debug)]
+enum Command {
+    /// Run a scenario file (.toml or .json) instead of the command line options
+    Run { scenario: std::path::PathBuf },
 }
 
 fn get_locked_gnss_time_secs(telemetry: &serde_json::Value) -> Result<f64> {
@@ -85,21 +405,19 @@ fn get_locked_gnss_time_secs(telemetry: &serde_json::Value) -> Result<f64> {
         .ok_or_else(|| anyhow::anyhow!("Telemetry has no clocks"))?;
     let gnss_clock = clocks
         .iter()
-        .filter(|x| x.get("name").unwrap().as_str().unwrap() == "gnss")
-        .next()
+        .find(|x| x.get("name").unwrap().as_str().unwrap() == "gnss")
         .ok_or_else(|| anyhow::anyhow!("Telemetry has no GNSS clock"))?;
-    let is_utc = gnss_clock.get("scale").map(|x| x.as_i64()).flatten() == Some(1);
+    let is_utc = gnss_clock.get("scale").and_then(|x| x.as_i64()) == Some(1);
     if !is_utc {
         return Err(anyhow::anyhow!("GNSS clock is not UTC"));
     }
-    let is_synchronized = gnss_clock.get("state").map(|x| x.as_i64()).flatten() == Some(2);
+    let is_synchronized = gnss_clock.get("state").and_then(|x| x.as_i64()) == Some(2);
     if !is_synchronized {
         return Err(anyhow::anyhow!("GNSS clock is not synchronized"));
     }
     let time = gnss_clock
         .get("time")
-        .map(|x| x.as_f64())
-        .flatten()
+        .and_then(|x| x.as_f64())
         .ok_or_else(|| anyhow::anyhow!("GNSS clock has no time"))?;
 
     Ok(time)
@@ -107,30 +425,46 @@ fn get_locked_gnss_time_secs(telemetry: &serde_json::Value) -> Result<f64> {
 
 fn get_reference(telemetry: &serde_json::Value) -> Option<LLA> {
     let reference = telemetry.get("ref")?.as_array()?;
-    Some(LLA::from_degs(
-        reference.get(0)?.as_f64()?,
-        reference.get(1)?.as_f64()?,
+    LLA::new(
+        Degrees(reference.first()?.as_f64()?),
+        Degrees(reference.get(1)?.as_f64()?),
         reference.get(2)?.as_f64()? as f32,
-    ))
+    )
+    .ok()
 }
 
 struct App {
     skypack: Arc<Skypack>,
-    rng: ThreadRng,
-    velocity_ned: nalgebra::Vector3<f32>,
-    delay: f32,
-    interval: Duration,
-    reference: Reference,
-    init_utc: f64,
-    h_noise: f32,
-    v_noise: f32,
+    /// Source of all randomness, seeded so that runs can be repeated. ChaCha8 gives the same stream for a seed
+    /// on every platform and rand version, unlike `StdRng`.
+    rng: ChaCha8Rng,
+    trajectory: Box<dyn Trajectory>,
+    /// GNSS time of the trajectory start
+    start_utc: f64,
+    /// Target position in the current tangent plane, and the trajectory position it corresponds to
+    target_ned: Ned<f32>,
+    trajectory_position: Ned<f32>,
+    sea_state: SeaState,
+    scheduler: Scheduler,
+    /// Last GNSS time read from the device, and when it was received
+    gnss_clock: Option<(f64, Instant)>,
+    reference: MovingReference,
+    skymate_reference: LLA,
+    noise: NoiseModel,
+    faults: FaultInjector,
+    latency: Latency,
+    extrapolate: bool,
+    rpy_unit: Option<RpyUnit>,
+    /// Move the tangent plane when SKYMATE reports a new reference
+    follow_skymate_reference: bool,
+    /// GNSS time at which the run ends
+    end_utc: Option<f64>,
+    finished: bool,
 }
 
 impl App {
     async fn run(&mut self) {
-        loop {
-            let now = Instant::now();
-
+        while !self.finished {
             match self.iteration().await {
                 Ok(_) => (),
                 Err(e) => {
@@ -138,9 +472,14 @@ impl App {
                 }
             }
 
-            let work_time = now.elapsed();
-            if work_time < self.interval {
-                sleep(self.interval - work_time);
+            if let Some((gnss_utc, read_at)) = self.gnss_clock.take() {
+                self.scheduler.lock_phase(gnss_utc, read_at);
+            }
+            if let Some(report) = self.scheduler.report() {
+                println!("{}", report);
+            }
+            if !self.finished {
+                self.scheduler.wait().await;
             }
         }
     }
@@ -148,7 +487,7 @@ impl App {
     async fn iteration(&mut self) -> Result<()> {
         //Fetch telemetry
         let telemetry = {
-            let response = self.skypack.get_telemetry_sync()?;
+            let response = self.skypack.get_telemetry().await?;
             if response.res != 0 {
                 None
             } else {
@@ -158,59 +497,249 @@ impl App {
         .ok_or_else(|| anyhow::anyhow!("Failed to fetch telemetry"))?;
 
         let skymate_utc = get_locked_gnss_time_secs(&telemetry)?;
-        let elapsed_secs = skymate_utc - self.init_utc - self.delay as f64;
-        let offset_tangent = self.velocity_ned * elapsed_secs as f32;
-        let lla = self.reference.tangent_to_lla(
-            offset_tangent
-                + Vector3::new(
-                    self.rng.random_range(-1_f32..1_f32) * self.h_noise / 2.,
-                    self.rng.random_range(-1_f32..1_f32) * self.h_noise / 2.,
-                    self.rng.random_range(-1_f32..1_f32) * self.v_noise / 2.,
-                ),
-        );
+        self.gnss_clock = Some((skymate_utc, Instant::now()));
+        if self.end_utc.is_some_and(|end_utc| skymate_utc >= end_utc) {
+            self.finished = true;
+            return Ok(());
+        }
+        //The target is measured some time before being sent
+        let measurement_utc = skymate_utc - self.latency.sample(&mut self.rng);
+        let state = self.trajectory.state(measurement_utc - self.start_utc);
+        //Trajectory displacements are applied as local NED so that the target follows the ellipsoid
+        self.target_ned += state.position - self.trajectory_position;
+        self.trajectory_position = state.position;
+
+        //Follow the target with the tangent plane, or a new SKYMATE reference, without a jump in position
+        let transform = match get_reference(&telemetry).filter(|_| self.follow_skymate_reference) {
+            Some(lla) if lla.lat().0 != self.skymate_reference.lat().0
+                || lla.lon().0 != self.skymate_reference.lon().0
+                || lla.altitude != self.skymate_reference.altitude =>
+            {
+                println!("SKYMATE Reference changed: {}", lla);
+                self.skymate_reference = lla;
+                Some(self.reference.reanchor(lla))
+            }
+            _ => self.reference.track(self.target_ned),
+        };
+        if let Some(transform) = transform {
+            self.target_ned = transform.position(self.target_ned);
+            println!("Tangent plane moved to {}", self.reference.reference().lla);
+        }
+
+        let time = measurement_utc - self.start_utc;
+        let motion = self.sea_state.motion(time);
+        let velocity = state.velocity + Ned::new(0., 0., -motion.heave_rate as f32);
+        let mut measured = self.target_ned
+            + Ned::new(0., 0., -motion.heave as f32)
+            + self.noise.sample(measurement_utc, &mut self.rng);
+        let mut timestamp = measurement_utc;
+        if self.extrapolate {
+            measured += velocity * (skymate_utc - measurement_utc) as f32;
+            timestamp = skymate_utc;
+        }
+
+        let update = Update {
+            lla: self.reference.reference().tangent_to_lla(measured),
+            velocity,
+            timestamp,
+        };
+        let (update, fired) = self.faults.apply(time, update, &mut self.rng);
+        for kind in fired {
+            println!("Fault: {}", kind);
+        }
+        let Some(update) = update else {
+            println!("Skipped");
+            return Ok(());
+        };
 
         self.skypack
-            .set_precision_landing_zone(lla, self.velocity_ned, skymate_utc)
+            .set_precision_landing_zone(
+                update.lla,
+                update.velocity,
+                self.rpy_unit
+                    .map_or([0.; 3], |unit| unit.encode(&Attitude::from_rpy(motion.roll, motion.pitch, state.heading))),
+                update.timestamp,
+            )
             .await?;
-        println!(
-            "Sent {}, {}, {}",
-            lla.latitude.to_degrees(),
-            lla.longitude.to_degrees(),
-            lla.altitude
-        );
+        println!("Sent {}", update.lla);
 
         Ok(())
     }
 }
 
+impl Args {
+    fn scenario(&self) -> Scenario {
+        let heading = Some(self.vel_degrees as f64);
+        let speed = self.vel as f64;
+        let trajectory = match self.trajectory {
+            TrajectoryKind::Stationary => TrajectoryConfig::Stationary { heading },
+            TrajectoryKind::Straight => TrajectoryConfig::Straight { speed, heading },
+            TrajectoryKind::Circle => TrajectoryConfig::Circle {
+                speed,
+                heading,
+                turn_rate: self.turn_rate as f64,
+            },
+            TrajectoryKind::FigureEight => TrajectoryConfig::FigureEight {
+                heading,
+                size: self.eight_size as f64,
+                period: self.eight_period as f64,
+            },
+            TrajectoryKind::Ramp => TrajectoryConfig::Ramp {
+                heading,
+                initial_speed: Some(speed),
+                final_speed: self.final_vel as f64,
+                acceleration: self.accel as f64,
+            },
+            TrajectoryKind::Route => TrajectoryConfig::Route {
+                waypoints: self.waypoint.clone(),
+                speed,
+                max_turn_rate: self.max_turn_rate as f64,
+                looping: self.loop_route,
+            },
+        };
+        let sea_state = match self.sea_state {
+            SeaStateKind::Calm => SeaStateConfig::Calm,
+            SeaStateKind::Sinusoids => SeaStateConfig::Sinusoids {
+                heave_amplitude: self.heave_amplitude as f64,
+                heave_period: self.heave_period as f64,
+                roll_amplitude: self.roll_amplitude as f64,
+                roll_period: self.roll_period as f64,
+                pitch_amplitude: self.pitch_amplitude as f64,
+                pitch_period: self.pitch_period as f64,
+            },
+            SeaStateKind::Jonswap => SeaStateConfig::Jonswap {
+                wave_height: self.wave_height as f64,
+                wave_period: self.wave_period as f64,
+                wave_direction: self.wave_direction as f64,
+            },
+        };
+        let f64_of = |value: Option<f32>| value.map(|v| v as f64);
+
+        Scenario {
+            rate: self.rate as f64,
+            phase_lock: self.phase_lock,
+            duration: f64_of(self.duration),
+            delay: self.delay as f64,
+            magnetic_headings: self.vel_magnetic,
+            wmm: self.wmm.clone(),
+            reference: ReferenceConfig {
+                source: if self.reference.is_some() { ReferenceSource::Fixed } else { ReferenceSource::Skymate },
+                position: self.reference,
+                reanchor_distance: self.reanchor_distance.map(f64::from),
+            },
+            target: TargetConfig {
+                latitude: self.lat,
+                longitude: self.lon,
+                north: f64_of(self.north),
+                east: f64_of(self.east),
+                altitude: f64_of(self.alt),
+                altitude_datum: self.alt_datum.into(),
+                geoid: self.geoid.clone(),
+                geoid_interpolation: self.geoid_interpolation.into(),
+            },
+            trajectory,
+            events: self
+                .stop_after
+                .map(|at| Event {
+                    at: at as f64,
+                    trajectory: TrajectoryConfig::Stationary { heading: None },
+                })
+                .into_iter()
+                .collect(),
+            sea_state,
+            noise: NoiseConfig {
+                preset: self.noise.into(),
+                h_sigma: f64_of(self.h_sigma),
+                cep: f64_of(self.cep),
+                v_sigma: f64_of(self.v_sigma),
+                h_bias: f64_of(self.h_bias),
+                v_bias: f64_of(self.v_bias),
+                bias_time: f64_of(self.bias_time),
+                h_random_walk: f64_of(self.h_random_walk),
+                v_random_walk: f64_of(self.v_random_walk),
+                ne_correlation: self.ne_correlation as f64,
+            },
+            latency: LatencyConfig {
+                fixed: self.latency as f64,
+                jitter: self.jitter as f64,
+                distribution: self.jitter_distribution.into(),
+                extrapolate: self.extrapolate,
+            },
+            faults: self.fault.clone(),
+            rpy_unit: self.rpy_unit.map(Into::into),
+            seed: None,
+        }
+    }
+}
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let args = Args::parse();
     let addr = format!("{}:{}", args.ip, args.port);
-    let rng = rand::rng();
+    let scenario = match &args.command {
+        Some(Command::Run { scenario }) => Scenario::load(scenario),
+        None => {
+            let scenario = args.scenario();
+            scenario.validate().map(|_| scenario)
+        }
+    };
+    //Printed with Display, parse errors show the offending line
+    let scenario = match scenario {
+        Ok(scenario) => scenario,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(2);
+        }
+    };
+    println!("{}", scenario.describe());
+
+    let seed = args.seed.or(scenario.seed).unwrap_or_else(|| rand::rng().random());
+    println!("Seed: {} (repeat the run with --seed {})", seed, seed);
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    let geoid = scenario
+        .target
+        .geoid
+        .as_ref()
+        .map(|path| Geoid::load(path, scenario.target.geoid_interpolation))
+        .transpose()?;
+    let wmm = scenario.wmm.as_ref().map(Wmm::load).transpose()?;
     let skypack = Skypack::new("0.0.0.0:0", &addr).await?;
-    let angle = args.vel_degrees.to_radians();
-    let velocity_ned = Vector3::new(angle.cos() * args.vel, angle.sin() * args.vel, 0.0);
 
-    //Use SKYMATE initial position
-    println!("Acquiring SKYMATE Reference...");
-    let reference_lla = loop {
-        match skypack.get_telemetry().await {
-            Ok(telemetry) => match get_reference(&telemetry.data.unwrap()) {
-                None => continue,
-                Some(lla) => break lla,
-                _ => continue,
-            },
-            _ => continue,
+    let reference_lla = match (scenario.reference.source, scenario.reference.position) {
+        (ReferenceSource::Fixed, Some(position)) => {
+            println!("Fixed Reference: {}", position);
+            position
+        }
+        _ => {
+            //Use SKYMATE initial position
+            println!("Acquiring SKYMATE Reference...");
+            let reference_lla = loop {
+                match skypack.get_telemetry().await {
+                    Ok(telemetry) => match get_reference(&telemetry.data.unwrap()) {
+                        None => continue,
+                        Some(lla) => break lla,
+                    },
+                    _ => continue,
+                }
+            };
+            println!("SKYMATE Reference: {}", reference_lla);
+            reference_lla
         }
     };
+
     let reference = Reference::new(reference_lla);
-    println!(
-        "SKYMATE Reference: {}, {}, {}",
-        reference_lla.latitude.to_degrees(),
-        reference_lla.longitude.to_degrees(),
-        reference_lla.altitude
-    );
+    let mut origin_ned = scenario.target.start_ned(&reference, geoid.as_ref())?;
+    if let Some(geoid) = &geoid {
+        let start = reference.tangent_to_lla(origin_ned);
+        println!(
+            "Target altitude: {} m ellipsoid, {} m MSL",
+            start.altitude,
+            start.altitude - geoid.undulation(&start)
+        );
+    }
+    if scenario.target.has_position() {
+        println!("Target start: {}", reference.tangent_to_lla(origin_ned));
+    }
 
     println!("Acquiring SKYMATE UTC Time...");
     let init_utc = loop {
@@ -240,18 +769,40 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         );
     }
 
+    let mut declination = Radians(0.);
+    if let (true, Some(wmm)) = (scenario.magnetic_headings, &wmm) {
+        declination = wmm.field(&reference_lla, init_utc)?.declination;
+        println!("Magnetic declination: {:.2} degrees", declination.to_degrees());
+    }
+    let motion = scenario.trajectory(&reference, declination)?;
+    if let Some(start) = motion.start {
+        origin_ned += start;
+    }
+    let start_utc = init_utc + scenario.delay;
+
     let mut app = App {
         skypack,
+        sea_state: scenario.sea_state.build(&mut rng)?,
         rng,
-        velocity_ned,
-        delay: args.delay,
-        interval: Duration::from_secs_f32(1.0 / args.rate),
-        reference,
-        init_utc,
-        h_noise: args.h_noise,
-        v_noise: args.v_noise,
+        trajectory: motion.trajectory,
+        start_utc,
+        target_ned: origin_ned,
+        trajectory_position: Ned::default(),
+        scheduler: Scheduler::new(Duration::from_secs_f64(1.0 / scenario.rate), scenario.phase_lock),
+        gnss_clock: None,
+        reference: MovingReference::new(reference_lla, scenario.reference.reanchor_distance.map(|d| d as f32)),
+        skymate_reference: reference_lla,
+        noise: NoiseModel::new(scenario.noise.parameters())?,
+        faults: FaultInjector::new(scenario.faults.clone()),
+        latency: scenario.latency.latency()?,
+        extrapolate: scenario.latency.extrapolate,
+        rpy_unit: scenario.rpy_unit,
+        follow_skymate_reference: scenario.reference.source == ReferenceSource::Skymate,
+        end_utc: scenario.duration.map(|duration| start_utc + duration),
+        finished: false,
     };
     app.run().await;
+    println!("Finished");
 
     Ok(())
 }
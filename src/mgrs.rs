@@ -0,0 +1,208 @@
+use core::fmt;
+use core::str::FromStr;
+
+use crate::prelude::*;
+use crate::utm::{Hemisphere, Utm, UtmError};
+
+/// Latitude bands of 8°, from 80°S, X being stretched to 84°N
+const BANDS: &[u8] = b"CDEFGHJKLMNPQRSTUVWX";
+/// 100 km column letters, cycling every three zones
+const COLUMN_SETS: [&[u8]; 3] = [b"ABCDEFGH", b"JKLMNPQR", b"STUVWXYZ"];
+/// 100 km row letters, offset by five in even zones
+const ROWS: &[u8] = b"ABCDEFGHJKLMNPQRSTUV";
+
+/// Military Grid Reference System coordinate, e.g. `31U DQ 48251 11932`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Mgrs {
+    pub zone: u8,
+    pub band: char,
+    pub column: char,
+    pub row: char,
+    /// meters within the 100 km square
+    pub easting: f64,
+    /// meters within the 100 km square
+    pub northing: f64,
+    /// digits per axis, 0 (100 km) to 5 (1 m)
+    pub precision: u8,
+}
+
+fn band_of(latitude: Degrees) -> char {
+    let index = ((latitude.0 + 80.) / 8.).floor().clamp(0., (BANDS.len() - 1) as f64) as usize;
+    BANDS[index] as char
+}
+
+impl Mgrs {
+    pub fn from_utm(utm: &Utm, latitude: Degrees, precision: u8) -> Self {
+        let precision = precision.min(5);
+        let column_index = (utm.easting / 100e3).floor() as usize;
+        let row_index = (utm.northing / 100e3).floor() as usize;
+        let row_offset = if utm.zone.is_multiple_of(2) { 5 } else { 0 };
+        let resolution = 10_f64.powi(5 - precision as i32);
+        let truncate = |v: f64| (v.rem_euclid(100e3) / resolution).floor() * resolution;
+
+        Self {
+            zone: utm.zone,
+            band: band_of(latitude),
+            column: COLUMN_SETS[(utm.zone as usize - 1) % 3][(column_index + 7) % 8] as char,
+            row: ROWS[(row_index + row_offset) % ROWS.len()] as char,
+            easting: truncate(utm.easting),
+            northing: truncate(utm.northing),
+            precision,
+        }
+    }
+
+    /// South-west corner of the grid square, as UTM
+    pub fn to_utm(&self) -> Result<Utm, UtmError> {
+        let invalid = || UtmError::InvalidMgrs(self.to_string());
+        if !(1..=60).contains(&self.zone) {
+            return Err(UtmError::InvalidZone(self.zone));
+        }
+        let band_index = BANDS.iter().position(|&b| b as char == self.band).ok_or_else(invalid)?;
+        let column_index = COLUMN_SETS[(self.zone as usize - 1) % 3]
+            .iter()
+            .position(|&c| c as char == self.column)
+            .ok_or_else(invalid)?;
+        let row_offset = if self.zone.is_multiple_of(2) { 5 } else { 0 };
+        let row_index = ROWS.iter().position(|&r| r as char == self.row).ok_or_else(invalid)?;
+        let row_index = (row_index + ROWS.len() - row_offset) % ROWS.len();
+
+        let hemisphere = if self.band >= 'N' { Hemisphere::North } else { Hemisphere::South };
+        let easting = (column_index + 1) as f64 * 100e3 + self.easting;
+        let northing_in_cycle = row_index as f64 * 100e3 + self.northing;
+
+        //Row letters repeat every 2000 km: pick the cycle that lands in the latitude band
+        let band_south = LLA::from_degs(Degrees(band_index as f64 * 8. - 80.), Degrees(self.zone as f64 * 6. - 183.), 0.);
+        let band_south_northing = band_south.to_utm_zone(self.zone)?.northing;
+        let mut northing = northing_in_cycle;
+        while northing < band_south_northing - 100e3 {
+            northing += 2000e3;
+        }
+
+        Ok(Utm {
+            zone: self.zone,
+            hemisphere,
+            easting,
+            northing,
+        })
+    }
+}
+
+impl fmt::Display for Mgrs {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:02}{} {}{}", self.zone, self.band, self.column, self.row)?;
+        if self.precision > 0 {
+            let divisor = 10_f64.powi(5 - self.precision as i32);
+            let width = self.precision as usize;
+            write!(
+                f,
+                " {:0width$} {:0width$}",
+                (self.easting / divisor).floor() as u32,
+                (self.northing / divisor).floor() as u32,
+                width = width
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Mgrs {
+    type Err = UtmError;
+
+    /// Accepts references with or without spaces, e.g. `31UDQ4825111932` or `31U DQ 48251 11932`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || UtmError::InvalidMgrs(s.to_owned());
+        let compact: String = s.chars().filter(|c| !c.is_whitespace()).collect::<String>().to_ascii_uppercase();
+        let zone_digits = compact.chars().take_while(|c| c.is_ascii_digit()).count();
+        if !(1..=2).contains(&zone_digits) {
+            return Err(invalid());
+        }
+        let zone: u8 = compact[..zone_digits].parse().map_err(|_| invalid())?;
+        let mut letters = compact[zone_digits..].chars();
+        let band = letters.next().ok_or_else(invalid)?;
+        let column = letters.next().ok_or_else(invalid)?;
+        let row = letters.next().ok_or_else(invalid)?;
+        let digits = letters.as_str();
+        if !digits.len().is_multiple_of(2) || digits.len() > 10 || !digits.chars().all(|c| c.is_ascii_digit()) {
+            return Err(invalid());
+        }
+        let precision = (digits.len() / 2) as u8;
+        let scale = 10_f64.powi(5 - precision as i32);
+        let parse = |d: &str| if d.is_empty() { Ok(0.) } else { d.parse::<f64>().map(|v| v * scale).map_err(|_| invalid()) };
+        let (easting, northing) = digits.split_at(precision as usize);
+
+        let mgrs = Self {
+            zone,
+            band,
+            column,
+            row,
+            easting: parse(easting)?,
+            northing: parse(northing)?,
+            precision,
+        };
+        //Validates zone and letters
+        mgrs.to_utm()?;
+        Ok(mgrs)
+    }
+}
+
+impl LLA {
+    /// MGRS reference with `precision` digits per axis (5 is 1 m, 0 is the 100 km square)
+    pub fn to_mgrs(&self, precision: u8) -> Result<Mgrs, UtmError> {
+        Ok(Mgrs::from_utm(&self.to_utm()?, self.lat().to_degrees(), precision))
+    }
+
+    /// South-west corner of the MGRS grid square
+    pub fn from_mgrs(mgrs: &Mgrs, altitude: f32) -> Result<LLA, UtmError> {
+        LLA::from_utm(&mgrs.to_utm()?, altitude)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lla::lla;
+
+    #[test]
+    fn published_point() {
+        //GeographicLib GeoConvert documentation: 33.3 44.4 is 38SMB4484 at 1 km
+        let mgrs = lla(33.3, 44.4, 0.).to_mgrs(5).unwrap();
+        assert_eq!(mgrs.to_string(), "38S MB 44140 84706");
+        assert_eq!(lla(33.3, 44.4, 0.).to_mgrs(2).unwrap().to_string(), "38S MB 44 84");
+    }
+
+    #[test]
+    fn band_letters() {
+        for (latitude, band) in [(-80., 'C'), (-33.3, 'H'), (-0.1, 'M'), (0., 'N'), (33.3, 'S'), (60., 'V'), (72., 'X'), (83.9, 'X')] {
+            assert_eq!(lla(latitude, 10., 0.).to_mgrs(5).unwrap().band, band, "{}", latitude);
+        }
+        assert_eq!(lla(60., 5., 0.).to_mgrs(0).unwrap().to_string(), "32V KM");
+        for longitude in [8., 20., 30., 40.] {
+            let mgrs = lla(78., longitude, 0.).to_mgrs(0).unwrap();
+            assert!(mgrs.zone % 2 == 1 && mgrs.band == 'X', "{}", mgrs);
+        }
+    }
+
+    #[test]
+    fn parse_and_display_round_trip() {
+        for text in ["38S MB 44140 84706", "32V KM", "33X VG 123 456"] {
+            assert_eq!(text.parse::<Mgrs>().unwrap().to_string(), text);
+        }
+        assert_eq!("38smb4414084706".parse::<Mgrs>().unwrap(), "38S MB 44140 84706".parse().unwrap());
+        assert!("38SMB441408470".parse::<Mgrs>().is_err());
+        assert!("38IMB4414084706".parse::<Mgrs>().is_err());
+        assert!("61SMB4414084706".parse::<Mgrs>().is_err());
+    }
+
+    /// The 2000 km row letter cycle is resolved with the band letter
+    #[test]
+    fn band_recovers_northing() {
+        for (latitude, longitude) in [(33.3, 44.4), (-33.3, 45.6), (-79.5, -70.), (-0.5, 3.), (0.5, 3.), (63.9, 5.), (78., 20.), (83.9, 30.), (45.5, -73.5)] {
+            let position = lla(latitude, longitude, 0.);
+            let mgrs: Mgrs = position.to_mgrs(5).unwrap().to_string().parse().unwrap();
+            let corner = LLA::from_mgrs(&mgrs, 0.).unwrap();
+            //Truncated to the 1 m square
+            let offset = corner.offset_to(&position);
+            assert!(offset.north().abs() < 1.5 && offset.east().abs() < 1.5, "{} {}: {} is {:?} away", latitude, longitude, mgrs, offset);
+        }
+    }
+}
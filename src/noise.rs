@@ -0,0 +1,284 @@
+//! GNSS position error models.
+//!
+//! The error on each NED axis is the sum of white noise, a first-order Gauss-Markov bias and a random walk.
+//! The driving noises are correlated between axes through a correlation matrix. All sigmas are 1-sigma, in
+//! meters (meters per √s for the random walk).
+
+use core::f64::consts::TAU;
+
+use nalgebra::Cholesky;
+use rand::Rng;
+
+use crate::prelude::*;
+
+/// CEP (50% circle) over the per-axis 1-sigma of a circular horizontal error, √(2 ln 2)
+pub const CEP_PER_SIGMA: f64 = 1.177_410_022_515_474_5;
+
+#[derive(thiserror::Error, Debug, Clone, Copy, PartialEq)]
+pub enum NoiseError {
+    #[error("Axis correlation matrix is not positive definite")]
+    InvalidCorrelation,
+    #[error("Correlation time must be positive, got {0} s")]
+    InvalidCorrelationTime(f64),
+    #[error("Sigma must be finite and non-negative, got {0}")]
+    InvalidSigma(f64),
+    #[error("Correlation coefficient {0} is outside [-1, 1]")]
+    CorrelationOutOfRange(f64),
+}
+
+/// Error parameters of the north, east and down axes
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct NoiseParameters {
+    /// white noise, meters
+    pub white: Vector3<f64>,
+    /// steady-state Gauss-Markov bias, meters
+    pub bias: Vector3<f64>,
+    /// bias correlation time, seconds
+    pub correlation_time: f64,
+    /// random walk, m/√s
+    pub random_walk: Vector3<f64>,
+    /// correlation coefficients between the axes driving noises
+    pub correlation: Matrix3<f64>,
+}
+
+/// Typical receiver performances
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum NoisePreset {
+    /// No error
+    None,
+    /// Single frequency standalone GPS
+    Gps,
+    /// GPS with SBAS corrections
+    Sbas,
+    /// RTK, float ambiguities
+    RtkFloat,
+    /// RTK, fixed ambiguities
+    RtkFixed,
+}
+
+impl NoiseParameters {
+    /// (horizontal, vertical) 1-sigma values, the same on both horizontal axes, uncorrelated axes
+    pub fn new(white: (f64, f64), bias: (f64, f64), correlation_time: f64, random_walk: (f64, f64)) -> Self {
+        let axes = |(horizontal, vertical): (f64, f64)| Vector3::new(horizontal, horizontal, vertical);
+        Self {
+            white: axes(white),
+            bias: axes(bias),
+            correlation_time,
+            random_walk: axes(random_walk),
+            correlation: Matrix3::identity(),
+        }
+    }
+
+    pub fn preset(preset: NoisePreset) -> Self {
+        match preset {
+            NoisePreset::None => Self::new((0., 0.), (0., 0.), 1., (0., 0.)),
+            NoisePreset::Gps => Self::new((0.3, 0.5), (1.5, 3.), 300., (0., 0.)),
+            NoisePreset::Sbas => Self::new((0.2, 0.3), (0.6, 1.), 300., (0., 0.)),
+            NoisePreset::RtkFloat => Self::new((0.05, 0.08), (0.2, 0.3), 60., (0.002, 0.003)),
+            NoisePreset::RtkFixed => Self::new((0.005, 0.01), (0.01, 0.015), 30., (0., 0.)),
+        }
+    }
+
+    /// Horizontal white noise from its CEP
+    pub fn with_cep(mut self, cep: f64) -> Self {
+        self.white.x = cep / CEP_PER_SIGMA;
+        self.white.y = cep / CEP_PER_SIGMA;
+        self
+    }
+
+    /// Correlation coefficient between the north and east errors
+    pub fn with_north_east_correlation(mut self, coefficient: f64) -> Self {
+        self.correlation.m12 = coefficient;
+        self.correlation.m21 = coefficient;
+        self
+    }
+}
+
+/// Stateful error generator, sampled once per update
+pub struct NoiseModel {
+    parameters: NoiseParameters,
+    /// lower triangular factor of the correlation matrix
+    correlation_factor: Matrix3<f64>,
+    bias: Vector3<f64>,
+    walk: Vector3<f64>,
+    last_time: Option<f64>,
+}
+
+/// Standard normal sample (Box-Muller)
+pub fn gaussian(rng: &mut impl Rng) -> f64 {
+    let u: f64 = 1. - rng.random::<f64>();
+    let v: f64 = rng.random();
+    (-2. * u.ln()).sqrt() * (TAU * v).cos()
+}
+
+impl NoiseModel {
+    pub fn new(parameters: NoiseParameters) -> Result<Self, NoiseError> {
+        if parameters.correlation_time <= 0. || parameters.correlation_time.is_nan() {
+            return Err(NoiseError::InvalidCorrelationTime(parameters.correlation_time));
+        }
+        let sigmas = parameters.white.iter().chain(parameters.bias.iter()).chain(parameters.random_walk.iter());
+        if let Some(&sigma) = sigmas.into_iter().find(|sigma| !sigma.is_finite() || **sigma < 0.) {
+            return Err(NoiseError::InvalidSigma(sigma));
+        }
+        if let Some(&coefficient) = parameters.correlation.iter().find(|coefficient| !(-1. ..=1.).contains(*coefficient)) {
+            return Err(NoiseError::CorrelationOutOfRange(coefficient));
+        }
+        let correlation_factor = Cholesky::new(parameters.correlation)
+            .ok_or(NoiseError::InvalidCorrelation)?
+            .unpack();
+        Ok(Self {
+            parameters,
+            correlation_factor,
+            bias: Vector3::zeros(),
+            walk: Vector3::zeros(),
+            last_time: None,
+        })
+    }
+
+    /// Correlated standard normal vector
+    fn draw(&self, rng: &mut impl Rng) -> Vector3<f64> {
+        self.correlation_factor * Vector3::new(gaussian(rng), gaussian(rng), gaussian(rng))
+    }
+
+    /// Position error at `time` (seconds)
+    pub fn sample(&mut self, time: f64, rng: &mut impl Rng) -> Ned<f32> {
+        let p = &self.parameters;
+        match self.last_time {
+            //Start with the bias at its steady state
+            None => self.bias = self.draw(rng).component_mul(&p.bias),
+            Some(last_time) => {
+                let dt = (time - last_time).max(0.);
+                let phi = (-dt / p.correlation_time).exp();
+                let bias_drive = self.draw(rng).component_mul(&p.bias) * (1. - phi * phi).sqrt();
+                let walk_drive = self.draw(rng).component_mul(&p.random_walk) * dt.sqrt();
+                self.bias = self.bias * phi + bias_drive;
+                self.walk += walk_drive;
+            }
+        }
+        self.last_time = Some(time);
+        let white = self.draw(rng).component_mul(&self.parameters.white);
+        let error = white + self.bias + self.walk;
+        Ned::new(error.x as f32, error.y as f32, error.z as f32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    use super::*;
+
+    const SAMPLES: usize = 20_000;
+
+    fn parameters() -> NoiseParameters {
+        NoiseParameters::new((0., 0.), (0., 0.), 1., (0., 0.))
+    }
+
+    /// Errors sampled every `dt` seconds
+    fn errors(parameters: NoiseParameters, dt: f64, count: usize, seed: u64) -> Vec<Vector3<f64>> {
+        let mut model = NoiseModel::new(parameters).unwrap();
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        (0..count).map(|i| model.sample(i as f64 * dt, &mut rng).as_vector().cast()).collect()
+    }
+
+    fn mean(values: &[f64]) -> f64 {
+        values.iter().sum::<f64>() / values.len() as f64
+    }
+
+    fn covariance(a: &[f64], b: &[f64]) -> f64 {
+        let (mean_a, mean_b) = (mean(a), mean(b));
+        a.iter().zip(b).map(|(a, b)| (a - mean_a) * (b - mean_b)).sum::<f64>() / (a.len() - 1) as f64
+    }
+
+    fn correlation(a: &[f64], b: &[f64]) -> f64 {
+        covariance(a, b) / (covariance(a, a) * covariance(b, b)).sqrt()
+    }
+
+    fn axis(samples: &[Vector3<f64>], axis: usize) -> Vec<f64> {
+        samples.iter().map(|sample| sample[axis]).collect()
+    }
+
+    #[test]
+    fn white_noise_sigma() {
+        let samples = errors(NoiseParameters { white: Vector3::new(0.5, 0.5, 2.), ..parameters() }, 1., SAMPLES, 1);
+        for (index, sigma) in [(0, 0.5), (1, 0.5), (2, 2.)] {
+            let values = axis(&samples, index);
+            assert!(mean(&values).abs() < 0.05 * sigma);
+            assert!((covariance(&values, &values).sqrt() / sigma - 1.).abs() < 0.03, "axis {}", index);
+            //Uncorrelated in time
+            assert!(correlation(&values[1..], &values[..SAMPLES - 1]).abs() < 0.03);
+        }
+    }
+
+    #[test]
+    fn gauss_markov_variance_and_correlation() {
+        let (sigma, tau, dt) = (3., 10., 1.);
+        let samples = errors(NoiseParameters { bias: Vector3::new(0., 0., sigma), correlation_time: tau, ..parameters() }, dt, 100_000, 2);
+        let values = axis(&samples, 2);
+        assert!((covariance(&values, &values).sqrt() / sigma - 1.).abs() < 0.05);
+        for lag in [1, 5, 20] {
+            let expected = (-(lag as f64) * dt / tau).exp();
+            let actual = correlation(&values[lag..], &values[..values.len() - lag]);
+            assert!((actual - expected).abs() < 0.03, "lag {}: {} instead of {}", lag, actual, expected);
+        }
+    }
+
+    #[test]
+    fn random_walk_variance_grows_with_time() {
+        let q = 0.2;
+        let parameters = NoiseParameters { random_walk: Vector3::new(q, q, q), ..parameters() };
+        let mut rng = ChaCha8Rng::seed_from_u64(3);
+        let times = [0., 4., 16., 64.];
+        let runs: Vec<Vec<f64>> = (0..4000)
+            .map(|_| {
+                let mut model = NoiseModel::new(parameters).unwrap();
+                times.iter().map(|&time| model.sample(time, &mut rng).north() as f64).collect()
+            })
+            .collect();
+        assert!(runs.iter().all(|run| run[0] == 0.));
+        for (index, time) in times.iter().enumerate().skip(1) {
+            let values: Vec<f64> = runs.iter().map(|run| run[index]).collect();
+            let variance = values.iter().map(|v| v * v).sum::<f64>() / values.len() as f64;
+            assert!((variance / (q * q * time) - 1.).abs() < 0.08, "{} s: {} instead of {}", time, variance, q * q * time);
+        }
+    }
+
+    #[test]
+    fn north_east_correlation() {
+        let white = NoiseParameters { white: Vector3::new(1., 2., 1.), ..parameters() };
+        let samples = errors(white.with_north_east_correlation(0.6), 1., SAMPLES, 4);
+        let (north, east, down) = (axis(&samples, 0), axis(&samples, 1), axis(&samples, 2));
+        assert!((correlation(&north, &east) - 0.6).abs() < 0.02);
+        assert!(correlation(&north, &down).abs() < 0.02 && correlation(&east, &down).abs() < 0.02);
+        //The correlation does not change the per-axis sigmas
+        assert!((covariance(&east, &east).sqrt() / 2. - 1.).abs() < 0.03);
+        let negative = errors(white.with_north_east_correlation(-0.9), 1., SAMPLES, 5);
+        assert!((correlation(&axis(&negative, 0), &axis(&negative, 1)) + 0.9).abs() < 0.02);
+    }
+
+    #[test]
+    fn cep_contains_half_the_horizontal_errors() {
+        let cep = 1.5;
+        let parameters = parameters().with_cep(cep);
+        assert!((parameters.white.x * CEP_PER_SIGMA - cep).abs() < 1e-12);
+        assert!((CEP_PER_SIGMA - (2. * 2_f64.ln()).sqrt()).abs() < 1e-15);
+        let samples = errors(parameters, 1., 100_000, 6);
+        let inside = samples.iter().filter(|sample| sample.xy().norm() <= cep).count();
+        assert!((inside as f64 / samples.len() as f64 - 0.5).abs() < 0.01, "{} inside", inside);
+    }
+
+    #[test]
+    fn rejects_invalid_parameters() {
+        let new = |parameters: NoiseParameters| NoiseModel::new(parameters).err();
+        assert_eq!(new(NoiseParameters { white: Vector3::new(-0.1, 0., 0.), ..parameters() }), Some(NoiseError::InvalidSigma(-0.1)));
+        assert!(matches!(new(NoiseParameters { bias: Vector3::new(0., f64::NAN, 0.), ..parameters() }), Some(NoiseError::InvalidSigma(_))));
+        assert_eq!(new(NoiseParameters { random_walk: Vector3::new(0., 0., f64::INFINITY), ..parameters() }), Some(NoiseError::InvalidSigma(f64::INFINITY)));
+        assert_eq!(new(parameters().with_north_east_correlation(1.2)), Some(NoiseError::CorrelationOutOfRange(1.2)));
+        assert!(matches!(new(parameters().with_north_east_correlation(f64::NAN)), Some(NoiseError::CorrelationOutOfRange(_))));
+        assert_eq!(new(parameters().with_north_east_correlation(1.)), Some(NoiseError::InvalidCorrelation));
+        assert_eq!(new(NoiseParameters { correlation_time: 0., ..parameters() }), Some(NoiseError::InvalidCorrelationTime(0.)));
+        assert!(new(parameters().with_north_east_correlation(-0.99)).is_none());
+    }
+}
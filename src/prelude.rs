@@ -1,6 +1,18 @@
 pub use nalgebra::{Vector2, Vector3, Matrix3};
-pub use crate::lla::LLA;
+pub use crate::attitude::Attitude;
+pub use crate::batch::{BatchError, ECEFBatch, LLABatch, NedBatch, ecef_to_lla_batch, lla_to_ecef_batch};
+pub use crate::angle::{Degrees, Radians};
+pub use crate::lla::{LLA, LLAError};
 pub use crate::ecef::ECEF;
 pub use crate::earth::Earth;
-pub use crate::reference::Reference;
-
+pub use crate::format::{CoordinateFormat, ParseCoordinateError};
+pub use crate::geoid::Geoid;
+pub use crate::geodesic::{Geodesic, GeodesicError};
+pub use crate::mgrs::Mgrs;
+pub use crate::reference::{MovingReference, Reference, ReferenceTransform};
+pub use crate::utm::{Hemisphere, Utm, UtmError};
+pub use crate::eci::ECI;
+pub use crate::wmm::{MagneticField, Wmm, WmmError};
+pub use crate::frame::{Body, EcefVec, EciVec, Enu, Ned};
+#[cfg(feature = "serde")]
+pub use crate::serialization::{degrees, radians};
@@ -15,38 +15,324 @@ impl Reference {
         Reference {
             lla: reference,
             ecef: reference.to_ecef(),
-            sin_lat: reference.latitude.sin(),
-            sin_lon: reference.longitude.sin(),
-            cos_lat: reference.latitude.cos(),
-            cos_lon: reference.longitude.cos(),
+            sin_lat: reference.lat().0.sin(),
+            sin_lon: reference.lon().0.sin(),
+            cos_lat: reference.lat().0.cos(),
+            cos_lon: reference.lon().0.cos(),
         }
     }
 
-    pub fn ecef_to_tangent(&self, ecef: ECEF) -> Vector3<f32> {
-        let dxyz_local_ecef0 = ecef.x - self.ecef.x;
-        let dxyz_local_ecef1 = ecef.y - self.ecef.y;
-        let dxyz_local_ecef2 = ecef.z - self.ecef.z;
+    /// Rotation taking ECEF vectors into the NED tangent frame
+    pub fn ecef_to_ned_rotation(&self) -> Matrix3<f64> {
+        Matrix3::new(
+            -self.sin_lat * self.cos_lon, -self.sin_lat * self.sin_lon, self.cos_lat,
+            -self.sin_lon, self.cos_lon, 0.,
+            -self.cos_lat * self.cos_lon, -self.cos_lat * self.sin_lon, -self.sin_lat,
+        )
+    }
 
-        Vector3::new(
-            ((-self.sin_lat * self.cos_lon) * dxyz_local_ecef0 + (-self.sin_lat * self.sin_lon) * dxyz_local_ecef1 + self.cos_lat * dxyz_local_ecef2) as f32,
-            ((-self.sin_lon) * dxyz_local_ecef0 + self.cos_lon * dxyz_local_ecef1 + (0.) * dxyz_local_ecef2) as f32,
-            ((-self.cos_lat * self.cos_lon) * dxyz_local_ecef0 + (-self.cos_lat * self.sin_lon) * dxyz_local_ecef1 + (-self.sin_lat) * dxyz_local_ecef2) as f32,
+    /// Rotation taking NED tangent vectors into ECEF, the transpose of `ecef_to_ned_rotation`
+    pub fn ned_to_ecef_rotation(&self) -> Matrix3<f64> {
+        self.ecef_to_ned_rotation().transpose()
+    }
+
+    pub fn ecef_vec_to_ned(&self, v: EcefVec) -> Ned<f32> {
+        Ned::new(
+            ((-self.sin_lat * self.cos_lon) * v.x() + (-self.sin_lat * self.sin_lon) * v.y() + self.cos_lat * v.z()) as f32,
+            ((-self.sin_lon) * v.x() + self.cos_lon * v.y() + (0.) * v.z()) as f32,
+            ((-self.cos_lat * self.cos_lon) * v.x() + (-self.cos_lat * self.sin_lon) * v.y() + (-self.sin_lat) * v.z()) as f32,
         )
     }
 
-    pub fn lla_to_tangent(&self, lla: LLA) -> Vector3<f32> {
+    pub fn ned_to_ecef_vec(&self, ned: Ned<f32>) -> EcefVec {
+        let (n, e, d) = (ned.north() as f64, ned.east() as f64, ned.down() as f64);
+        EcefVec::new(
+            -self.sin_lat * self.cos_lon * n - self.sin_lon * e - self.cos_lat * self.cos_lon * d,
+            -self.sin_lat * self.sin_lon * n + self.cos_lon * e - self.cos_lat * self.sin_lon * d,
+            self.cos_lat * n - self.sin_lat * d,
+        )
+    }
+
+    pub fn ecef_to_tangent(&self, ecef: ECEF) -> Ned<f32> {
+        self.ecef_vec_to_ned(EcefVec::new(ecef.x - self.ecef.x, ecef.y - self.ecef.y, ecef.z - self.ecef.z))
+    }
+
+    pub fn lla_to_tangent(&self, lla: LLA) -> Ned<f32> {
         self.ecef_to_tangent(lla.to_ecef())
     }
 
-    pub fn tangent_to_ecef(&self, tangent: Vector3<f32>) -> ECEF {
-        ECEF::new(
-            -self.sin_lat * self.cos_lon * tangent.x as f64 - self.sin_lon * tangent.y as f64 - self.cos_lat * self.cos_lon * tangent.z as f64,
-            -self.sin_lat * self.sin_lon * tangent.x as f64 + self.cos_lon * tangent.y as f64 - self.cos_lat * self.sin_lon * tangent.z as f64,
-            self.cos_lat * tangent.x as f64 - self.sin_lat * tangent.z as f64,
+    /// Absolute ECEF position of a tangent plane point, origin included. Displacements go through
+    /// `ned_to_ecef_vec` instead.
+    pub fn tangent_to_ecef_position(&self, tangent: Ned<f32>) -> ECEF {
+        self.ecef + self.ned_to_ecef_vec(tangent)
+    }
+
+    pub fn tangent_to_lla(&self, tangent: Ned<f32>) -> LLA {
+        self.tangent_to_ecef_position(tangent).to_lla()
+    }
+
+    pub fn ned_velocity_to_ecef(&self, velocity: Ned<f32>) -> EcefVec {
+        self.ned_to_ecef_vec(velocity)
+    }
+
+    pub fn ecef_velocity_to_ned(&self, velocity: EcefVec) -> Ned<f32> {
+        self.ecef_vec_to_ned(velocity)
+    }
+
+    /// Meridian and prime-vertical radii of curvature at the reference latitude (meters)
+    fn radii_of_curvature(&self) -> (f64, f64) {
+        (Earth::meridian_radius(self.lla.lat()), Earth::prime_vertical_radius(self.lla.lat()))
+    }
+
+    /// NED velocity to geodetic rates at the reference: [latitude (rad/s), longitude (rad/s), altitude (m/s)]
+    pub fn ned_velocity_to_geodetic_rates(&self, velocity: Ned<f32>) -> Vector3<f64> {
+        let (m, n) = self.radii_of_curvature();
+        let h = self.lla.altitude as f64;
+        Vector3::new(
+            velocity.north() as f64 / (m + h),
+            velocity.east() as f64 / ((n + h) * self.cos_lat),
+            -velocity.down() as f64,
+        )
+    }
+
+    /// Inverse of `ned_velocity_to_geodetic_rates`
+    pub fn geodetic_rates_to_ned_velocity(&self, rates: Vector3<f64>) -> Ned<f32> {
+        let (m, n) = self.radii_of_curvature();
+        let h = self.lla.altitude as f64;
+        Ned::new(
+            (rates.x * (m + h)) as f32,
+            (rates.y * (n + h) * self.cos_lat) as f32,
+            -rates.z as f32,
         )
     }
 
-    pub fn tangent_to_lla(&self, tangent: Vector3<f32>) -> LLA {
-        (self.tangent_to_ecef(tangent) + self.ecef).to_lla()
+    /// Rotates a 3x3 covariance expressed in NED into ECEF
+    pub fn ned_covariance_to_ecef(&self, covariance: Matrix3<f64>) -> Matrix3<f64> {
+        let r = self.ned_to_ecef_rotation();
+        r * covariance * r.transpose()
+    }
+
+    /// Rotates a 3x3 covariance expressed in ECEF into NED
+    pub fn ecef_covariance_to_ned(&self, covariance: Matrix3<f64>) -> Matrix3<f64> {
+        let r = self.ecef_to_ned_rotation();
+        r * covariance * r.transpose()
+    }
+
+    pub fn body_to_ecef_vec(&self, attitude: &Attitude, body: Body<f32>) -> EcefVec {
+        self.ned_to_ecef_vec(attitude.body_to_ned(body))
+    }
+
+    /// Position of a target seen at `offset` in the body frame of a vehicle located at `origin`,
+    /// with `attitude` taken relative to this reference's NED axes
+    pub fn body_offset_to_lla(&self, origin: LLA, attitude: &Attitude, offset: Body<f32>) -> LLA {
+        (origin.to_ecef() + self.body_to_ecef_vec(attitude, offset)).to_lla()
+    }
+
+    /// Normal gravity at `tangent`, along the local vertical of the reference
+    pub fn gravity_ned(&self, tangent: Ned<f32>) -> Ned<f32> {
+        let height = self.lla.altitude as f64 - tangent.down() as f64;
+        Ned::new(0., 0., Earth::normal_gravity(self.lla.lat(), height) as f32)
+    }
+
+    pub fn gravity_ecef(&self, tangent: Ned<f32>) -> EcefVec {
+        self.ned_to_ecef_vec(self.gravity_ned(tangent))
+    }
+}
+
+/// Rigid transform taking tangent coordinates of one `Reference` into another
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ReferenceTransform {
+    rotation: Matrix3<f64>,
+    translation: Vector3<f64>,
+}
+
+impl ReferenceTransform {
+    /// Transforms a position: the same point on Earth, expressed in the target reference
+    pub fn position(&self, tangent: Ned<f32>) -> Ned<f32> {
+        let v = self.rotation * tangent.as_vector().cast::<f64>() + self.translation;
+        Ned::new(v.x as f32, v.y as f32, v.z as f32)
+    }
+
+    /// Transforms a free vector (velocity, acceleration, offset), rotation only
+    pub fn vector(&self, vector: Ned<f32>) -> Ned<f32> {
+        let v = self.rotation * vector.as_vector().cast::<f64>();
+        Ned::new(v.x as f32, v.y as f32, v.z as f32)
+    }
+
+    pub fn inverse(&self) -> ReferenceTransform {
+        let rotation = self.rotation.transpose();
+        ReferenceTransform {
+            rotation,
+            translation: -(rotation * self.translation),
+        }
+    }
+}
+
+impl Reference {
+    /// Transform from this reference's tangent plane to `to`'s, without going through geodetic coordinates
+    pub fn transform_to(&self, to: &Reference) -> ReferenceTransform {
+        let to_rotation = to.ecef_to_ned_rotation();
+        let origin_offset = Vector3::new(self.ecef.x - to.ecef.x, self.ecef.y - to.ecef.y, self.ecef.z - to.ecef.z);
+        ReferenceTransform {
+            rotation: to_rotation * self.ned_to_ecef_rotation(),
+            translation: to_rotation * origin_offset,
+        }
+    }
+
+    pub fn rebase_position(&self, tangent: Ned<f32>, to: &Reference) -> Ned<f32> {
+        self.transform_to(to).position(tangent)
+    }
+
+    pub fn rebase_vector(&self, vector: Ned<f32>, to: &Reference) -> Ned<f32> {
+        self.transform_to(to).vector(vector)
+    }
+}
+
+/// Reference that follows a travelling point, re-anchoring under it once it drifts further than a threshold
+/// so that the tangent plane stays close to the ellipsoid. Without a threshold it only moves on `reanchor`.
+#[derive(Clone, Debug)]
+pub struct MovingReference {
+    reference: Reference,
+    /// meters, horizontal
+    threshold: Option<f32>,
+}
+
+impl MovingReference {
+    pub fn new(anchor: LLA, threshold: Option<f32>) -> Self {
+        Self {
+            reference: Reference::new(anchor),
+            threshold,
+        }
+    }
+
+    pub fn reference(&self) -> &Reference {
+        &self.reference
+    }
+
+    /// Moves the origin to `anchor`. Tangent positions and velocities held by the caller must be carried over
+    /// with the returned transform.
+    pub fn reanchor(&mut self, anchor: LLA) -> ReferenceTransform {
+        let reference = Reference::new(anchor);
+        let transform = self.reference.transform_to(&reference);
+        self.reference = reference;
+        transform
+    }
+
+    /// Re-anchors under `position` (keeping the current origin altitude) when it is further than the threshold
+    /// from the origin. Returns the transform to apply to held tangent coordinates if it did.
+    pub fn track(&mut self, position: Ned<f32>) -> Option<ReferenceTransform> {
+        let threshold = self.threshold?;
+        if position.north().hypot(position.east()) <= threshold {
+            return None;
+        }
+        let mut anchor = self.reference.tangent_to_lla(position);
+        anchor.altitude = self.reference.lla.altitude;
+        Some(self.reanchor(anchor))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn moving_reference_only_tracks_with_a_threshold() {
+        let anchor = LLA::from_degs(Degrees(45.), Degrees(-73.), 50.);
+        let far = Ned::new(3000., 4000., -20.);
+        let mut fixed = MovingReference::new(anchor, None);
+        assert!(fixed.track(far).is_none());
+        assert_eq!(fixed.reference().lla.lat(), anchor.lat());
+
+        let mut moving = MovingReference::new(anchor, Some(1000.));
+        assert!(moving.track(Ned::new(600., 800., 0.)).is_none());
+        let transform = moving.track(far).unwrap();
+        //The new origin sits under the point, at the old origin altitude
+        let moved = transform.position(far);
+        assert!(moved.north().hypot(moved.east()) < 0.01, "{:?}", moved);
+        assert_eq!(moving.reference().lla.altitude, 50.);
+    }
+
+    fn reference() -> Reference {
+        Reference::new(LLA::from_degs(Degrees(-33.86), Degrees(151.21), 120.))
+    }
+
+    fn assert_ned(actual: Ned<f32>, expected: Ned<f32>, tolerance: f32) {
+        assert!((actual - expected).as_vector().norm() <= tolerance, "{:?} instead of {:?}", actual, expected);
+    }
+
+    #[test]
+    fn rotation_is_orthonormal() {
+        let r = reference().ecef_to_ned_rotation();
+        assert!((r * r.transpose() - Matrix3::identity()).norm() < 1e-12);
+        assert!((r.determinant() - 1.).abs() < 1e-12);
+        assert_eq!(reference().ned_to_ecef_rotation(), r.transpose());
+        //Down points to the centre side of the ellipsoid normal
+        let down = reference().ned_to_ecef_vec(Ned::new(0., 0., 1.));
+        let position = reference().ecef;
+        assert!(down.x() * position.x + down.y() * position.y + down.z() * position.z < 0.);
+    }
+
+    #[test]
+    fn velocity_round_trip() {
+        let reference = reference();
+        let velocity = Ned::new(12.5, -3.25, 0.75);
+        let ecef = reference.ned_velocity_to_ecef(velocity);
+        assert!((ecef.as_vector().norm() - velocity.as_vector().norm() as f64).abs() < 1e-5);
+        assert_ned(reference.ecef_velocity_to_ned(ecef), velocity, 1e-5);
+    }
+
+    #[test]
+    fn geodetic_rates_match_finite_differences() {
+        let reference = reference();
+        let lla = reference.lla;
+        let rates = Vector3::new(2e-7, -3e-7, 1.5);
+        let dt = 1e-2;
+        let moved = |sign: f64| {
+            LLA::from_rads(lla.lat() + Radians(sign * rates.x * dt), lla.lon() + Radians(sign * rates.y * dt), lla.altitude + (sign * rates.z * dt) as f32).to_ecef()
+        };
+        let difference = moved(1.) - moved(-1.);
+        let velocity = reference.ecef_vec_to_ned(EcefVec::new(difference.x, difference.y, difference.z) / (2. * dt));
+        assert_ned(reference.geodetic_rates_to_ned_velocity(rates), velocity, 1e-3);
+        let back = reference.ned_velocity_to_geodetic_rates(reference.geodetic_rates_to_ned_velocity(rates));
+        assert!((back - rates).component_div(&rates).abs().max() < 1e-6, "{:?}", back);
+    }
+
+    #[test]
+    fn covariance_keeps_its_trace() {
+        let reference = reference();
+        let covariance = Matrix3::new(4., 0.5, 0.1, 0.5, 9., -0.3, 0.1, -0.3, 1.);
+        let ecef = reference.ned_covariance_to_ecef(covariance);
+        assert!((ecef.trace() - covariance.trace()).abs() < 1e-9);
+        assert!((ecef - ecef.transpose()).norm() < 1e-12);
+        assert!((reference.ecef_covariance_to_ned(ecef) - covariance).norm() < 1e-9);
+    }
+
+    #[test]
+    fn transform_between_references() {
+        let from = reference();
+        let to = Reference::new(LLA::from_degs(Degrees(-33.85), Degrees(151.23), 80.));
+        let transform = from.transform_to(&to);
+        let point = Ned::new(500., -250., -30.);
+        assert_ned(transform.position(point), to.lla_to_tangent(from.tangent_to_lla(point)), 1e-3);
+        assert_ned(transform.inverse().position(transform.position(point)), point, 1e-3);
+        //Vectors only rotate
+        let velocity = Ned::new(5., 5., 0.);
+        assert_ned(transform.vector(velocity), to.ecef_vec_to_ned(from.ned_to_ecef_vec(velocity)), 1e-5);
+    }
+
+    #[test]
+    fn reanchor_keeps_positions_continuous() {
+        let mut moving = MovingReference::new(reference().lla, Some(1000.));
+        let point = Ned::new(1200., 900., -15.);
+        let before = moving.reference().tangent_to_lla(point);
+        let transform = moving.track(point).unwrap();
+        let after = moving.reference().tangent_to_lla(transform.position(point));
+        assert!(before.offset_to(&after).as_vector().norm() < 1e-3, "{:?}", before.offset_to(&after));
+        //An explicit reanchor too
+        let other = Ned::new(-40., 60., 2.);
+        let before = moving.reference().tangent_to_lla(other);
+        let transform = moving.reanchor(LLA::from_degs(Degrees(-33.9), Degrees(151.2), 0.));
+        let after = moving.reference().tangent_to_lla(transform.position(other));
+        assert!(before.offset_to(&after).as_vector().norm() < 1e-3);
     }
 }
@@ -0,0 +1,390 @@
+//! Waypoint route following.
+//!
+//! The route is flown leg by leg at each leg's speed, corners being rounded with circular arcs so that the
+//! turn rate stays below a maximum (arcs are tightened on legs too short for them).
+
+use core::f64::consts::PI;
+use core::fmt;
+use core::str::FromStr;
+
+use nalgebra::Vector2;
+
+use crate::prelude::*;
+use crate::trajectory::{Trajectory, TrajectoryState};
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq)]
+pub enum RouteError {
+    #[error("A route needs at least two waypoints")]
+    TooFewWaypoints,
+    #[error("Waypoints {0} and {1} are at the same position")]
+    ZeroLengthLeg(usize, usize),
+    #[error("Leg to waypoint {0} has a non-positive speed")]
+    InvalidSpeed(usize),
+    #[error("Cannot parse waypoint '{0}'")]
+    Invalid(String),
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum WaypointPosition {
+    Lla(LLA),
+    /// Offset from the SKYMATE reference, only the horizontal part is used
+    Ned(Ned<f32>),
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Waypoint {
+    pub position: WaypointPosition,
+    /// speed of the leg towards this waypoint (m/s), the default speed when `None`
+    pub speed: Option<f64>,
+}
+
+impl fmt::Display for Waypoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.position {
+            WaypointPosition::Lla(lla) => write!(f, "{}", lla)?,
+            WaypointPosition::Ned(ned) => write!(f, "ned:{},{}", ned.north(), ned.east())?,
+        }
+        if let Some(speed) = self.speed {
+            write!(f, "@{}", speed)?;
+        }
+        Ok(())
+    }
+}
+
+/// `lat, lon` in any notation accepted by `LLA`, or `ned:north,east` in meters from the reference,
+/// optionally followed by `@speed` in m/s
+impl FromStr for Waypoint {
+    type Err = RouteError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || RouteError::Invalid(s.to_owned());
+        let (position, speed) = match s.rsplit_once('@') {
+            Some((position, speed)) => (position, Some(speed.trim().parse::<f64>().map_err(|_| invalid())?)),
+            None => (s, None),
+        };
+        let position = match position.trim().strip_prefix("ned:") {
+            Some(offset) => {
+                let mut components = offset.split(',').map(|c| c.trim().parse::<f32>());
+                match (components.next(), components.next(), components.next()) {
+                    (Some(Ok(north)), Some(Ok(east)), None) => WaypointPosition::Ned(Ned::new(north, east, 0.)),
+                    _ => return Err(invalid()),
+                }
+            }
+            None => WaypointPosition::Lla(position.parse().map_err(|_| invalid())?),
+        };
+        Ok(Self { position, speed })
+    }
+}
+
+impl Waypoint {
+    /// Horizontal position in the tangent plane of `reference`
+    pub fn to_tangent(self, reference: &Reference) -> Ned<f32> {
+        let ned = match self.position {
+            WaypointPosition::Lla(lla) => reference.lla_to_tangent(lla),
+            WaypointPosition::Ned(ned) => ned,
+        };
+        Ned::new(ned.north(), ned.east(), 0.)
+    }
+}
+
+fn direction(heading: f64) -> Vector2<f64> {
+    Vector2::new(heading.cos(), heading.sin())
+}
+
+/// Unit vector to the right of `heading`
+fn right_of(heading: f64) -> Vector2<f64> {
+    Vector2::new(-heading.sin(), heading.cos())
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Segment {
+    Line {
+        start: Vector2<f64>,
+        heading: f64,
+        length: f64,
+        speed: f64,
+    },
+    /// Turn from `heading` by `sweep` radians, positive to the right
+    Arc {
+        center: Vector2<f64>,
+        radius: f64,
+        heading: f64,
+        sweep: f64,
+        speed: f64,
+    },
+}
+
+impl Segment {
+    fn duration(&self) -> f64 {
+        match *self {
+            Segment::Line { length, speed, .. } => length / speed,
+            Segment::Arc { radius, sweep, speed, .. } => radius * sweep.abs() / speed,
+        }
+    }
+
+    /// (position, heading, speed) `time` seconds into the segment
+    fn at(&self, time: f64) -> (Vector2<f64>, f64, f64) {
+        match *self {
+            Segment::Line { start, heading, speed, .. } => (start + direction(heading) * speed * time, heading, speed),
+            Segment::Arc {
+                center,
+                radius,
+                heading,
+                sweep,
+                speed,
+            } => {
+                let heading = heading + sweep.signum() * speed * time / radius;
+                (center - right_of(heading) * sweep.signum() * radius, heading, speed)
+            }
+        }
+    }
+}
+
+/// Trajectory through waypoints, relative to the first one
+pub struct Route {
+    /// first lap, from the first waypoint
+    segments: Vec<Segment>,
+    /// following laps of a looping route, from the end of the turn at the first waypoint
+    cycle: Vec<Segment>,
+}
+
+impl Route {
+    /// `points` are (tangent position, speed of the leg towards it); the first speed is used by the closing
+    /// leg of a looping route. `max_turn_rate` is in rad/s.
+    pub fn new(points: &[(Ned<f32>, f64)], max_turn_rate: f64, looping: bool) -> Result<Self, RouteError> {
+        if points.len() < 2 {
+            return Err(RouteError::TooFewWaypoints);
+        }
+        let origin = Vector2::new(points[0].0.north() as f64, points[0].0.east() as f64);
+        let mut vertices: Vec<(Vector2<f64>, f64)> = points
+            .iter()
+            .map(|(ned, speed)| (Vector2::new(ned.north() as f64, ned.east() as f64) - origin, *speed))
+            .collect();
+        if looping {
+            vertices.push(vertices[0]);
+        }
+        let legs = vertices.len() - 1;
+        for (index, (_, speed)) in vertices.iter().enumerate().skip(1) {
+            if *speed <= 0. || speed.is_nan() {
+                return Err(RouteError::InvalidSpeed(index % points.len()));
+            }
+        }
+
+        //(start, end, heading, speed, length) of each leg
+        let leg = |index: usize| {
+            let (start, end) = (vertices[index].0, vertices[index + 1].0);
+            let delta = end - start;
+            (start, end, delta.y.atan2(delta.x), vertices[index + 1].1, delta.norm())
+        };
+        for index in 0..legs {
+            if leg(index).4 < 1e-3 {
+                return Err(RouteError::ZeroLengthLeg(index % points.len(), (index + 1) % points.len()));
+            }
+        }
+
+        //Corner at the end of leg `index` into the next one: (tangent distance, arc)
+        let corner = |index: usize, next: usize| {
+            let (_, vertex, heading_in, speed_in, length_in) = leg(index);
+            let (_, _, heading_out, speed_out, length_out) = leg(next);
+            let sweep = (heading_out - heading_in + PI).rem_euclid(2. * PI) - PI;
+            if sweep.abs() < 1e-9 {
+                return (0., None);
+            }
+            let speed = speed_in.min(speed_out);
+            let half_tan = (sweep.abs() / 2.).tan();
+            let distance = (speed / max_turn_rate * half_tan).min(length_in.min(length_out) / 2.);
+            let radius = distance / half_tan;
+            let arc_start = vertex - direction(heading_in) * distance;
+            let arc = Segment::Arc {
+                center: arc_start + right_of(heading_in) * sweep.signum() * radius,
+                radius,
+                heading: heading_in,
+                sweep,
+                speed,
+            };
+            (distance, Some(arc))
+        };
+
+        //Corners at the end of each leg, the last one wrapping around when looping
+        let corners: Vec<(f64, Option<Segment>)> = (0..legs)
+            .map(|index| match (index + 1 < legs, looping) {
+                (true, _) => corner(index, index + 1),
+                (false, true) => corner(index, 0),
+                (false, false) => (0., None),
+            })
+            .collect();
+
+        let lap = |first_cut: f64| {
+            let mut segments = Vec::new();
+            for index in 0..legs {
+                let (start, _, heading, speed, length) = leg(index);
+                let cut_in = if index == 0 { first_cut } else { corners[index - 1].0 };
+                let (cut_out, arc) = corners[index];
+                segments.push(Segment::Line {
+                    start: start + direction(heading) * cut_in,
+                    heading,
+                    length: length - cut_in - cut_out,
+                    speed,
+                });
+                segments.extend(arc);
+            }
+            segments
+        };
+
+        Ok(Self {
+            segments: lap(0.),
+            cycle: if looping { lap(corners[legs - 1].0) } else { Vec::new() },
+        })
+    }
+
+    /// State `time` seconds into `segments`, clamped to their end
+    fn state_in(segments: &[Segment], mut time: f64) -> TrajectoryState {
+        for (index, segment) in segments.iter().enumerate() {
+            let duration = segment.duration();
+            if time <= duration || index + 1 == segments.len() {
+                let (position, heading, speed) = segment.at(time.clamp(0., duration));
+                let velocity = direction(heading) * speed;
+                return TrajectoryState {
+                    position: Ned::new(position.x as f32, position.y as f32, 0.),
+                    velocity: Ned::new(velocity.x as f32, velocity.y as f32, 0.),
+                    heading: Radians(heading).wrap_two_pi(),
+                };
+            }
+            time -= duration;
+        }
+        TrajectoryState::default()
+    }
+}
+
+impl Trajectory for Route {
+    fn state(&self, time: f64) -> TrajectoryState {
+        let lap_duration: f64 = self.segments.iter().map(Segment::duration).sum();
+        let cycle_duration: f64 = self.cycle.iter().map(Segment::duration).sum();
+        if time <= 0. || time >= lap_duration && cycle_duration == 0. {
+            //Waiting at the first waypoint or arrived at the last one
+            return TrajectoryState {
+                velocity: Ned::default(),
+                ..Self::state_in(&self.segments, time.max(0.))
+            };
+        }
+        if time < lap_duration {
+            Self::state_in(&self.segments, time)
+        } else {
+            Self::state_in(&self.cycle, (time - lap_duration).rem_euclid(cycle_duration))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(north: f32, east: f32, speed: f64) -> (Ned<f32>, f64) {
+        (Ned::new(north, east, 0.), speed)
+    }
+
+    fn assert_at(route: &Route, time: f64, north: f32, east: f32, heading: f64) {
+        let state = route.state(time);
+        assert!((state.position - Ned::new(north, east, 0.)).as_vector().norm() < 1e-2, "{:?} at {}", state, time);
+        assert!((state.heading - Degrees(heading).to_radians()).wrap_pi().0.abs() < 1e-6, "{:?} at {}", state, time);
+    }
+
+    /// Position is continuous and the heading changes no faster than `max_turn_rate`
+    fn assert_smooth(route: &Route, duration: f64, max_turn_rate: f64) {
+        let dt = 0.01;
+        let mut previous = route.state(0.);
+        for step in 1..(duration / dt) as usize {
+            let state = route.state(step as f64 * dt);
+            let jump = (state.position - previous.position).as_vector().norm() as f64;
+            assert!(jump <= previous.velocity.as_vector().norm().max(state.velocity.as_vector().norm()) as f64 * dt + 1e-3, "jump {} at {}", jump, step as f64 * dt);
+            assert!((state.heading - previous.heading).wrap_pi().0.abs() <= max_turn_rate * dt + 1e-6, "turn at {}", step as f64 * dt);
+            previous = state;
+        }
+    }
+
+    #[test]
+    fn right_angle_turn() {
+        //20 m/s at 0.1 rad/s turns on a 200 m radius, starting 200 m before the corner
+        let route = Route::new(&[point(0., 0., 20.), point(1000., 0., 20.), point(1000., 1000., 20.)], 0.1, false).unwrap();
+        let quarter = 200. * PI / 2. / 20.;
+        assert_at(&route, 40., 800., 0., 0.);
+        assert_at(&route, 40. + quarter / 2., 800. + 200. * (PI / 4.).sin() as f32, 200. - 200. * (PI / 4.).cos() as f32, 45.);
+        assert_at(&route, 40. + quarter, 1000., 200., 90.);
+        let end = 40. + quarter + 40.;
+        assert_at(&route, end, 1000., 1000., 90.);
+        assert_smooth(&route, end, 0.1);
+    }
+
+    #[test]
+    fn left_turn() {
+        let route = Route::new(&[point(0., 0., 10.), point(500., 0., 10.), point(500., -500., 10.)], 0.1, false).unwrap();
+        //100 m radius
+        let quarter = 100. * PI / 2. / 10.;
+        assert_at(&route, 40., 400., 0., 0.);
+        assert_at(&route, 40. + quarter, 500., -100., -90.);
+        assert_smooth(&route, 80. + quarter, 0.1);
+    }
+
+    #[test]
+    fn short_legs_tighten_the_arc() {
+        //The 200 m tangent distance does not fit on 100 m legs, the arc starts half way instead
+        let route = Route::new(&[point(0., 0., 20.), point(100., 0., 20.), point(100., 100., 20.)], 0.1, false).unwrap();
+        assert_at(&route, 2.5, 50., 0., 0.);
+        assert_at(&route, 2.5 + 50. * PI / 2. / 20., 100., 50., 90.);
+    }
+
+    #[test]
+    fn corner_uses_the_slower_leg_speed() {
+        //10 m/s on the second leg: 100 m radius
+        let route = Route::new(&[point(0., 0., 20.), point(1000., 0., 20.), point(1000., 1000., 10.)], 0.1, false).unwrap();
+        assert_at(&route, 45., 900., 0., 0.);
+        assert!((route.state(46.).velocity.as_vector().norm() - 10.).abs() < 1e-4);
+        assert_at(&route, 45. + 100. * PI / 2. / 10., 1000., 100., 90.);
+    }
+
+    #[test]
+    fn waits_and_stops() {
+        let route = Route::new(&[point(10., 10., 5.), point(10., 60., 5.)], 0.1, false).unwrap();
+        //Positions are relative to the first waypoint
+        assert_at(&route, -5., 0., 0., 90.);
+        assert_eq!(route.state(-5.).velocity, Ned::default());
+        assert_at(&route, 5., 0., 25., 90.);
+        assert_at(&route, 100., 0., 50., 90.);
+        assert_eq!(route.state(100.).velocity, Ned::default());
+    }
+
+    #[test]
+    fn looping_square() {
+        let side = 400.;
+        let points = [point(0., 0., 20.), point(side, 0., 20.), point(side, side, 20.), point(0., side, 20.)];
+        let route = Route::new(&points, 0.1, true).unwrap();
+        //The 200 m radius arcs use up the whole sides: a lap is four quarter arcs, the first one starting with half a side
+        let cycle = 4. * (200. * PI / 2. / 20.);
+        let first = 200. / 20. + cycle;
+        assert_at(&route, first, 200., 0., 0.);
+        assert_at(&route, first + cycle, 200., 0., 0.);
+        assert_at(&route, first + 2.5 * cycle, 200., side, 180.);
+        assert_smooth(&route, first + 2. * cycle, 0.1);
+    }
+
+    #[test]
+    fn rejects_bad_routes() {
+        assert_eq!(Route::new(&[point(0., 0., 5.)], 0.1, false).err(), Some(RouteError::TooFewWaypoints));
+        assert_eq!(Route::new(&[point(0., 0., 5.), point(0., 0., 5.)], 0.1, false).err(), Some(RouteError::ZeroLengthLeg(0, 1)));
+        assert_eq!(Route::new(&[point(0., 0., 5.), point(10., 0., 0.)], 0.1, false).err(), Some(RouteError::InvalidSpeed(1)));
+        assert_eq!(Route::new(&[point(0., 0., -1.), point(10., 0., 5.)], 0.1, true).err(), Some(RouteError::InvalidSpeed(0)));
+    }
+
+    #[test]
+    fn parses_waypoints() {
+        let waypoint: Waypoint = "ned:100,-50.5@12".parse().unwrap();
+        assert!(matches!(waypoint.position, WaypointPosition::Ned(ned) if ned == Ned::new(100., -50.5, 0.)));
+        assert_eq!(waypoint.speed, Some(12.));
+        let waypoint: Waypoint = "45.5, -73.25".parse().unwrap();
+        assert!(matches!(waypoint.position, WaypointPosition::Lla(lla) if (lla.lat().to_degrees().0 - 45.5).abs() < 1e-12));
+        assert_eq!(waypoint.speed, None);
+        for text in ["ned:1", "ned:1,2,3", "45.5, -73.25@fast", "somewhere"] {
+            assert!(text.parse::<Waypoint>().is_err(), "{}", text);
+        }
+    }
+}
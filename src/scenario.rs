@@ -0,0 +1,853 @@
+//! Scenario files describing a full landing test, in TOML or JSON.
+//!
+//! ```toml
+//! rate = 5            # Hz
+//! phase_lock = true   # updates on GNSS multiples of 1/rate
+//! duration = 120      # secs, runs until interrupted when absent
+//! faults = ["skip:5@t=40", "outlier:30@p=0.01"]
+//!
+//! [reference]
+//! source = "skymate"  # or "fixed" with position = "45.5, -73.5, 20"
+//!
+//! [target]
+//! latitude = 45.5012  # or north/east offsets from the reference, in meters
+//! longitude = -73.4978
+//! altitude = 12
+//! altitude_datum = "msl"
+//! geoid = "egm96-5.pgm"
+//!
+//! [trajectory]
+//! kind = "stationary"
+//!
+//! [[events]]
+//! at = 30
+//! kind = "straight"
+//! speed = 3
+//! heading = 90
+//!
+//! [noise]
+//! preset = "rtk-fixed"
+//! ```
+//!
+//! Angles are in degrees, distances in meters and times in seconds. Event times, like fault times, are
+//! counted from the start, after `delay`. Relative paths are relative to the scenario file.
+
+use core::fmt::Display;
+use core::str::FromStr;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Deserializer};
+
+use crate::fault::Fault;
+use crate::geoid::Interpolation;
+use crate::latency::{JitterDistribution, Latency, LatencyError};
+use crate::noise::{NoiseError, NoiseModel, NoiseParameters, NoisePreset};
+use crate::prelude::*;
+use crate::route::{Route, RouteError, Waypoint};
+use crate::sea_state::{Oscillation, SeaState, SeaStateError, check_period};
+use crate::trajectory::{ConstantTurn, ConstantVelocity, FigureEight, Piecewise, Ramp, Stationary, Trajectory};
+
+#[derive(thiserror::Error, Debug)]
+pub enum ScenarioError {
+    #[error("Cannot read scenario {0}: {1}")]
+    Io(PathBuf, std::io::Error),
+    #[error("Invalid scenario {0}: {1}")]
+    Toml(PathBuf, toml::de::Error),
+    #[error("Invalid scenario {0}: {1}")]
+    Json(PathBuf, serde_json::Error),
+    #[error("Unknown scenario format {0}, expected a .toml or .json file")]
+    UnknownFormat(PathBuf),
+    #[error("Invalid scenario: {0}")]
+    Invalid(String),
+    #[error("Invalid scenario route: {0}")]
+    Route(#[from] RouteError),
+    #[error("Invalid scenario sea state: {0}")]
+    SeaState(#[from] SeaStateError),
+    #[error("Invalid scenario noise: {0}")]
+    Noise(#[from] NoiseError),
+    #[error("Invalid scenario latency: {0}")]
+    Latency(#[from] LatencyError),
+}
+
+/// Deserializes through `FromStr`, so that values are written like on the command line
+fn parsed_option<'de, D: Deserializer<'de>, T: FromStr>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    T::Err: Display,
+{
+    Option::<String>::deserialize(deserializer)?
+        .map(|s| s.parse().map_err(serde::de::Error::custom))
+        .transpose()
+}
+
+fn parsed_vec<'de, D: Deserializer<'de>, T: FromStr>(deserializer: D) -> Result<Vec<T>, D::Error>
+where
+    T::Err: Display,
+{
+    Vec::<String>::deserialize(deserializer)?
+        .iter()
+        .map(|s| s.parse().map_err(serde::de::Error::custom))
+        .collect()
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AltitudeDatum {
+    /// Height above the WGS-84 ellipsoid
+    #[default]
+    Ellipsoid,
+    /// Height above mean sea level (geoid)
+    Msl,
+}
+
+/// Unit of the landing zone `rpy`, which the SKYMATE protocol documentation at hand does not state. The deck
+/// attitude is only sent when the unit is set, `[0, 0, 0]` is sent otherwise.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RpyUnit {
+    Degrees,
+    Radians,
+}
+
+impl RpyUnit {
+    /// Roll, pitch and yaw in this unit
+    pub fn encode(self, attitude: &Attitude) -> [f64; 3] {
+        let (roll, pitch, yaw) = attitude.rpy();
+        match self {
+            RpyUnit::Degrees => [roll.to_degrees().0, pitch.to_degrees().0, yaw.to_degrees().0],
+            RpyUnit::Radians => [roll.0, pitch.0, yaw.0],
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ReferenceSource {
+    /// The reference reported by SKYMATE
+    #[default]
+    Skymate,
+    /// `position`
+    Fixed,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ReferenceConfig {
+    #[serde(default)]
+    pub source: ReferenceSource,
+    #[serde(default, deserialize_with = "parsed_option")]
+    pub position: Option<LLA>,
+    /// horizontal distance after which the tangent plane is moved under the target, never when unset
+    #[serde(default)]
+    pub reanchor_distance: Option<f64>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TargetConfig {
+    /// absolute start position, degrees, the reference position when absent
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    /// start position as an offset from the reference, meters
+    pub north: Option<f64>,
+    pub east: Option<f64>,
+    /// the reference altitude when absent
+    pub altitude: Option<f64>,
+    #[serde(default)]
+    pub altitude_datum: AltitudeDatum,
+    pub geoid: Option<PathBuf>,
+    #[serde(default)]
+    pub geoid_interpolation: Interpolation,
+}
+
+impl TargetConfig {
+    /// The start position is set, absolutely or as an offset
+    pub fn has_position(&self) -> bool {
+        self.latitude.is_some() || self.north.is_some() || self.east.is_some()
+    }
+
+    /// Start position in the tangent plane of `reference`, at the reference when unset. MSL altitudes need the
+    /// `geoid`, read under the reference for north/east offsets.
+    pub fn start_ned(&self, reference: &Reference, geoid: Option<&Geoid>) -> Result<Ned<f32>, ScenarioError> {
+        let invalid = |message: String| Err(ScenarioError::Invalid(format!("target: {}", message)));
+        let mut start = match (self.latitude, self.longitude) {
+            (Some(latitude), Some(longitude)) => match LLA::new(Degrees(latitude), Degrees(longitude), reference.lla.altitude) {
+                Ok(lla) => lla,
+                Err(e) => return invalid(format!("position: {}", e)),
+            },
+            (None, None) => reference.lla,
+            _ => return invalid("a position needs both a latitude and a longitude".to_owned()),
+        };
+        if self.latitude.is_some() && (self.north.is_some() || self.east.is_some()) {
+            return invalid("a position cannot also have a north/east offset".to_owned());
+        }
+        start.altitude = match (self.altitude.map(|altitude| altitude as f32), self.altitude_datum, geoid) {
+            (None, _, _) => reference.lla.altitude,
+            (Some(altitude), AltitudeDatum::Ellipsoid, _) => altitude,
+            (Some(altitude), AltitudeDatum::Msl, Some(geoid)) => altitude + geoid.undulation(&start),
+            (Some(_), AltitudeDatum::Msl, None) => return invalid("an MSL altitude needs a geoid".to_owned()),
+        };
+        Ok(match self.latitude {
+            Some(_) => reference.lla_to_tangent(start),
+            None => Ned::new(
+                self.north.unwrap_or(0.) as f32,
+                self.east.unwrap_or(0.) as f32,
+                reference.lla.altitude - start.altitude,
+            ),
+        })
+    }
+}
+
+fn default_max_turn_rate() -> f64 {
+    10.
+}
+
+/// Headings default to the one the target has when the trajectory starts, 0 at the very start
+#[derive(Clone, Debug, Deserialize)]
+#[serde(tag = "kind", rename_all = "kebab-case", deny_unknown_fields)]
+pub enum TrajectoryConfig {
+    Stationary {
+        heading: Option<f64>,
+    },
+    Straight {
+        speed: f64,
+        heading: Option<f64>,
+    },
+    Circle {
+        speed: f64,
+        heading: Option<f64>,
+        /// degrees/s, positive to the right
+        turn_rate: f64,
+    },
+    FigureEight {
+        heading: Option<f64>,
+        size: f64,
+        period: f64,
+    },
+    Ramp {
+        heading: Option<f64>,
+        /// the current speed when absent
+        initial_speed: Option<f64>,
+        final_speed: f64,
+        acceleration: f64,
+    },
+    /// Starts at the first waypoint, only as the initial trajectory
+    Route {
+        #[serde(deserialize_with = "parsed_vec")]
+        waypoints: Vec<Waypoint>,
+        /// of legs without their own speed
+        speed: f64,
+        #[serde(default = "default_max_turn_rate")]
+        max_turn_rate: f64,
+        #[serde(default, rename = "loop")]
+        looping: bool,
+    },
+}
+
+impl Default for TrajectoryConfig {
+    fn default() -> Self {
+        TrajectoryConfig::Stationary { heading: None }
+    }
+}
+
+/// Target state the next trajectory starts from
+#[derive(Clone, Copy, Debug, Default)]
+struct Continuation {
+    heading: Radians,
+    speed: f64,
+}
+
+impl TrajectoryConfig {
+    fn name(&self) -> &'static str {
+        match self {
+            TrajectoryConfig::Stationary { .. } => "stationary",
+            TrajectoryConfig::Straight { .. } => "straight",
+            TrajectoryConfig::Circle { .. } => "circle",
+            TrajectoryConfig::FigureEight { .. } => "figure-eight",
+            TrajectoryConfig::Ramp { .. } => "ramp",
+            TrajectoryConfig::Route { .. } => "route",
+        }
+    }
+
+    fn validate(&self, context: &str) -> Result<(), ScenarioError> {
+        let invalid = |message: &str| Err(ScenarioError::Invalid(format!("{}: {}", context, message)));
+        let values = match *self {
+            TrajectoryConfig::Stationary { heading } => vec![heading],
+            TrajectoryConfig::Straight { speed, heading } => vec![Some(speed), heading],
+            TrajectoryConfig::Circle { speed, heading, turn_rate } => vec![Some(speed), heading, Some(turn_rate)],
+            TrajectoryConfig::FigureEight { heading, size, period } => vec![heading, Some(size), Some(period)],
+            TrajectoryConfig::Ramp {
+                heading,
+                initial_speed,
+                final_speed,
+                acceleration,
+            } => vec![heading, initial_speed, Some(final_speed), Some(acceleration)],
+            TrajectoryConfig::Route { speed, max_turn_rate, .. } => vec![Some(speed), Some(max_turn_rate)],
+        };
+        if values.into_iter().flatten().any(|value| !value.is_finite()) {
+            return invalid("values must be finite");
+        }
+        match self {
+            TrajectoryConfig::Straight { speed, .. } | TrajectoryConfig::Circle { speed, .. } if *speed < 0. => {
+                invalid("speed must not be negative")
+            }
+            TrajectoryConfig::FigureEight { period, .. } if *period <= 0. => invalid("period must be positive"),
+            TrajectoryConfig::Ramp { acceleration, .. } if *acceleration <= 0. => invalid("acceleration must be positive"),
+            TrajectoryConfig::Route { waypoints, .. } if waypoints.len() < 2 => invalid("a route needs at least two waypoints"),
+            TrajectoryConfig::Route { max_turn_rate, .. } if *max_turn_rate <= 0. => invalid("max_turn_rate must be positive"),
+            //The first speed is only used by the closing leg of a loop
+            TrajectoryConfig::Route { waypoints, speed, looping, .. }
+                if waypoints.iter().skip(usize::from(!looping)).map(|w| w.speed.unwrap_or(*speed)).any(|s| s.is_nan() || s <= 0.) =>
+            {
+                invalid("leg speeds must be positive")
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// `declination` turns magnetic headings into true ones, zero for true headings
+    fn build(&self, from: Continuation, declination: Radians) -> Box<dyn Trajectory> {
+        let heading = |heading: &Option<f64>| match heading {
+            Some(heading) => (Degrees(*heading).to_radians() + declination).wrap_two_pi(),
+            None => from.heading,
+        };
+        match self {
+            TrajectoryConfig::Stationary { heading: h } => Box::new(Stationary { heading: heading(h) }),
+            TrajectoryConfig::Straight { speed, heading: h } => Box::new(ConstantVelocity {
+                heading: heading(h),
+                speed: *speed,
+            }),
+            TrajectoryConfig::Circle {
+                speed,
+                heading: h,
+                turn_rate,
+            } => Box::new(ConstantTurn {
+                heading: heading(h),
+                speed: *speed,
+                turn_rate: turn_rate.to_radians(),
+            }),
+            TrajectoryConfig::FigureEight { heading: h, size, period } => Box::new(FigureEight {
+                heading: heading(h),
+                size: *size,
+                period: *period,
+            }),
+            TrajectoryConfig::Ramp {
+                heading: h,
+                initial_speed,
+                final_speed,
+                acceleration,
+            } => Box::new(Ramp {
+                heading: heading(h),
+                initial_speed: initial_speed.unwrap_or(from.speed),
+                final_speed: *final_speed,
+                acceleration: *acceleration,
+            }),
+            TrajectoryConfig::Route { .. } => unreachable!("routes are built by Scenario::trajectory"),
+        }
+    }
+}
+
+pub struct TargetMotion {
+    pub trajectory: Box<dyn Trajectory>,
+    /// start position relative to `reference` when the trajectory does not start at the target origin
+    pub start: Option<Ned<f32>>,
+}
+
+/// Trajectory change at a time
+#[derive(Clone, Debug, Deserialize)]
+pub struct Event {
+    /// secs
+    pub at: f64,
+    #[serde(flatten)]
+    pub trajectory: TrajectoryConfig,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(tag = "kind", rename_all = "kebab-case", deny_unknown_fields)]
+pub enum SeaStateConfig {
+    #[default]
+    Calm,
+    Sinusoids {
+        heave_amplitude: f64,
+        heave_period: f64,
+        roll_amplitude: f64,
+        roll_period: f64,
+        pitch_amplitude: f64,
+        pitch_period: f64,
+    },
+    Jonswap {
+        wave_height: f64,
+        wave_period: f64,
+        /// relative to the bow
+        wave_direction: f64,
+    },
+}
+
+impl SeaStateConfig {
+    pub fn validate(&self) -> Result<(), SeaStateError> {
+        match *self {
+            SeaStateConfig::Calm => Ok(()),
+            SeaStateConfig::Sinusoids {
+                heave_period,
+                roll_period,
+                pitch_period,
+                ..
+            } => [heave_period, roll_period, pitch_period].into_iter().try_for_each(|period| check_period(period).map(|_| ())),
+            SeaStateConfig::Jonswap { wave_period, .. } => check_period(wave_period).map(|_| ()),
+        }
+    }
+
+    pub fn build(&self, rng: &mut impl rand::Rng) -> Result<SeaState, SeaStateError> {
+        let oscillation = |amplitude: f64, period: f64| Oscillation { amplitude, period };
+        match *self {
+            SeaStateConfig::Calm => Ok(SeaState::default()),
+            SeaStateConfig::Sinusoids {
+                heave_amplitude,
+                heave_period,
+                roll_amplitude,
+                roll_period,
+                pitch_amplitude,
+                pitch_period,
+            } => SeaState::sinusoids(
+                oscillation(heave_amplitude, heave_period),
+                oscillation(roll_amplitude.to_radians(), roll_period),
+                oscillation(pitch_amplitude.to_radians(), pitch_period),
+            ),
+            SeaStateConfig::Jonswap {
+                wave_height,
+                wave_period,
+                wave_direction,
+            } => SeaState::jonswap(wave_height, wave_period, Degrees(wave_direction).to_radians(), rng),
+        }
+    }
+}
+
+/// A preset, each parameter being overridden when given
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NoiseConfig {
+    #[serde(default = "NoiseConfig::default_preset")]
+    pub preset: NoisePreset,
+    /// horizontal white noise, 1-sigma per axis
+    pub h_sigma: Option<f64>,
+    /// horizontal white noise as a CEP
+    pub cep: Option<f64>,
+    /// vertical white noise, 1-sigma
+    pub v_sigma: Option<f64>,
+    pub h_bias: Option<f64>,
+    pub v_bias: Option<f64>,
+    pub bias_time: Option<f64>,
+    pub h_random_walk: Option<f64>,
+    pub v_random_walk: Option<f64>,
+    #[serde(default)]
+    pub ne_correlation: f64,
+}
+
+impl NoiseConfig {
+    fn default_preset() -> NoisePreset {
+        NoisePreset::None
+    }
+
+    pub fn parameters(&self) -> NoiseParameters {
+        let mut parameters = NoiseParameters::preset(self.preset);
+        let set = |value: Option<f64>, parameter: &mut f64| {
+            if let Some(value) = value {
+                *parameter = value;
+            }
+        };
+        set(self.h_sigma, &mut parameters.white.x);
+        set(self.h_sigma, &mut parameters.white.y);
+        set(self.v_sigma, &mut parameters.white.z);
+        set(self.h_bias, &mut parameters.bias.x);
+        set(self.h_bias, &mut parameters.bias.y);
+        set(self.v_bias, &mut parameters.bias.z);
+        set(self.bias_time, &mut parameters.correlation_time);
+        set(self.h_random_walk, &mut parameters.random_walk.x);
+        set(self.h_random_walk, &mut parameters.random_walk.y);
+        set(self.v_random_walk, &mut parameters.random_walk.z);
+        if let Some(cep) = self.cep {
+            parameters = parameters.with_cep(cep);
+        }
+        parameters.with_north_east_correlation(self.ne_correlation)
+    }
+}
+
+impl Default for NoiseConfig {
+    fn default() -> Self {
+        Self {
+            preset: Self::default_preset(),
+            h_sigma: None,
+            cep: None,
+            v_sigma: None,
+            h_bias: None,
+            v_bias: None,
+            bias_time: None,
+            h_random_walk: None,
+            v_random_walk: None,
+            ne_correlation: 0.,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LatencyConfig {
+    #[serde(default)]
+    pub fixed: f64,
+    #[serde(default)]
+    pub jitter: f64,
+    #[serde(default = "LatencyConfig::default_distribution")]
+    pub distribution: JitterDistribution,
+    #[serde(default)]
+    pub extrapolate: bool,
+}
+
+impl LatencyConfig {
+    fn default_distribution() -> JitterDistribution {
+        JitterDistribution::Uniform
+    }
+
+    pub fn latency(&self) -> Result<Latency, LatencyError> {
+        Latency::new(self.fixed, self.jitter, self.distribution)
+    }
+}
+
+impl Default for LatencyConfig {
+    fn default() -> Self {
+        Self {
+            fixed: 0.,
+            jitter: 0.,
+            distribution: Self::default_distribution(),
+            extrapolate: false,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Scenario {
+    /// Hz
+    #[serde(default = "Scenario::default_rate")]
+    pub rate: f64,
+    /// align updates on GNSS time multiples of 1/rate
+    #[serde(default)]
+    pub phase_lock: bool,
+    /// secs
+    pub duration: Option<f64>,
+    /// secs before the start
+    #[serde(default)]
+    pub delay: f64,
+    /// headings are magnetic, converted with the World Magnetic Model `wmm`
+    #[serde(default)]
+    pub magnetic_headings: bool,
+    /// World Magnetic Model coefficient file (WMM.COF)
+    pub wmm: Option<PathBuf>,
+    #[serde(default)]
+    pub reference: ReferenceConfig,
+    #[serde(default)]
+    pub target: TargetConfig,
+    #[serde(default)]
+    pub trajectory: TrajectoryConfig,
+    #[serde(default)]
+    pub events: Vec<Event>,
+    #[serde(default)]
+    pub sea_state: SeaStateConfig,
+    #[serde(default)]
+    pub noise: NoiseConfig,
+    #[serde(default)]
+    pub latency: LatencyConfig,
+    #[serde(default, deserialize_with = "parsed_vec")]
+    pub faults: Vec<Fault>,
+    /// deck attitude is sent in this unit, not at all when absent
+    pub rpy_unit: Option<RpyUnit>,
+    /// seed of the random generators, random when absent
+    pub seed: Option<u64>,
+}
+
+impl Scenario {
+    fn default_rate() -> f64 {
+        1.
+    }
+
+    /// Reads a `.toml` or `.json` scenario and validates it
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ScenarioError> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path).map_err(|e| ScenarioError::Io(path.to_owned(), e))?;
+        let mut scenario: Scenario = match path.extension().and_then(|e| e.to_str()) {
+            Some("toml") => toml::from_str(&text).map_err(|e| ScenarioError::Toml(path.to_owned(), e))?,
+            Some("json") => serde_json::from_str(&text).map_err(|e| ScenarioError::Json(path.to_owned(), e))?,
+            _ => return Err(ScenarioError::UnknownFormat(path.to_owned())),
+        };
+        if let Some(directory) = path.parent() {
+            for file in [&mut scenario.wmm, &mut scenario.target.geoid].into_iter().flatten() {
+                *file = directory.join(&*file);
+            }
+        }
+        scenario.validate()?;
+        Ok(scenario)
+    }
+
+    /// Checks what the format alone cannot
+    pub fn validate(&self) -> Result<(), ScenarioError> {
+        let invalid = |message: String| Err(ScenarioError::Invalid(message));
+        if self.rate.is_nan() || self.rate <= 0. {
+            return invalid(format!("rate must be positive, got {}", self.rate));
+        }
+        if let Some(duration) = self.duration.filter(|d| d.is_nan() || *d <= 0.) {
+            return invalid(format!("duration must be positive, got {}", duration));
+        }
+        if self.reference.source == ReferenceSource::Fixed && self.reference.position.is_none() {
+            return invalid("a fixed reference needs a position".to_owned());
+        }
+        if let Some(distance) = self.reference.reanchor_distance.filter(|d| d.is_nan() || *d <= 0.) {
+            return invalid(format!("reanchor_distance must be positive, got {}", distance));
+        }
+        if !self.delay.is_finite() || self.delay < 0. {
+            return invalid(format!("delay must be finite and not negative, got {}", self.delay));
+        }
+        if self.noise.h_sigma.is_some() && self.noise.cep.is_some() {
+            return invalid("noise h_sigma and cep both set the horizontal white noise, give only one".to_owned());
+        }
+        NoiseModel::new(self.noise.parameters())?;
+        self.latency.latency()?;
+        self.sea_state.validate()?;
+        let target = &self.target;
+        if target.latitude.is_some() != target.longitude.is_some() {
+            return invalid("a target position needs both a latitude and a longitude".to_owned());
+        }
+        if let (Some(latitude), Some(longitude)) = (target.latitude, target.longitude) {
+            if let Err(e) = LLA::new(Degrees(latitude), Degrees(longitude), 0.) {
+                return invalid(format!("target position: {}", e));
+            }
+            if target.north.is_some() || target.east.is_some() {
+                return invalid("a target position cannot also have a north/east offset".to_owned());
+            }
+        }
+        if target.has_position() && matches!(self.trajectory, TrajectoryConfig::Route { .. }) {
+            return invalid("a route starts at its first waypoint, it cannot have a target position".to_owned());
+        }
+        if self.target.altitude.is_some() && self.target.altitude_datum == AltitudeDatum::Msl && self.target.geoid.is_none() {
+            return invalid("an MSL target altitude needs a geoid".to_owned());
+        }
+        if self.magnetic_headings && self.wmm.is_none() {
+            return invalid("magnetic headings need a wmm file".to_owned());
+        }
+        self.trajectory.validate("trajectory")?;
+        let mut previous = 0.;
+        for (index, event) in self.events.iter().enumerate() {
+            let context = format!("event {} (at {} s)", index + 1, event.at);
+            if !event.at.is_finite() || event.at <= previous {
+                return invalid(format!("{}: events must be in increasing time order, after the start", context));
+            }
+            if let TrajectoryConfig::Route { .. } = event.trajectory {
+                return invalid(format!("{}: a route can only be the initial trajectory", context));
+            }
+            event.trajectory.validate(&context)?;
+            previous = event.at;
+        }
+        Ok(())
+    }
+
+    /// The target trajectory with its events
+    pub fn trajectory(&self, reference: &Reference, declination: Radians) -> Result<TargetMotion, ScenarioError> {
+        let (initial, start): (Box<dyn Trajectory>, _) = match &self.trajectory {
+            TrajectoryConfig::Route {
+                waypoints,
+                speed,
+                max_turn_rate,
+                looping,
+            } => {
+                let points: Vec<(Ned<f32>, f64)> = waypoints
+                    .iter()
+                    .map(|waypoint| (waypoint.to_tangent(reference), waypoint.speed.unwrap_or(*speed)))
+                    .collect();
+                let route = Route::new(&points, max_turn_rate.to_radians(), *looping)?;
+                (Box::new(route), Some(points[0].0))
+            }
+            trajectory => (trajectory.build(Continuation::default(), declination), None),
+        };
+        if self.events.is_empty() {
+            return Ok(TargetMotion { trajectory: initial, start });
+        }
+
+        let mut piecewise = Piecewise::new();
+        let (mut current, mut started) = (initial, 0.);
+        for event in &self.events {
+            let state = current.state(event.at - started);
+            let from = Continuation {
+                heading: state.heading,
+                speed: (state.velocity.north() as f64).hypot(state.velocity.east() as f64),
+            };
+            let next = event.trajectory.build(from, declination);
+            piecewise = piecewise.then(event.at - started, current);
+            (current, started) = (next, event.at);
+        }
+        Ok(TargetMotion {
+            trajectory: Box::new(piecewise.then(0., current)),
+            start,
+        })
+    }
+
+    /// One line per setting, for the log
+    pub fn describe(&self) -> String {
+        let mut lines = vec![format!("Rate {} Hz, trajectory {}", self.rate, self.trajectory.name())];
+        for event in &self.events {
+            lines.push(format!("At {} s: {}", event.at, event.trajectory.name()));
+        }
+        for fault in &self.faults {
+            lines.push(format!("Fault {}", fault));
+        }
+        lines.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scenario(text: &str) -> Result<Scenario, ScenarioError> {
+        let scenario: Scenario = toml::from_str(text).unwrap();
+        scenario.validate().map(|_| scenario)
+    }
+
+    #[test]
+    fn accepts_the_documented_example() {
+        let example: String = include_str!("scenario.rs")
+            .lines()
+            .skip_while(|l| *l != "//! ```toml")
+            .skip(1)
+            .take_while(|l| *l != "//! ```")
+            .map(|l| l.trim_start_matches("//!").trim_start().to_owned() + "\n")
+            .collect();
+        let scenario = scenario(&example).unwrap();
+        assert_eq!(scenario.events.len(), 1);
+        assert_eq!(scenario.reference.reanchor_distance, None);
+    }
+
+    #[test]
+    fn rejects_invalid_noise() {
+        assert!(matches!(scenario("[noise]\nne_correlation = 2"), Err(ScenarioError::Noise(NoiseError::CorrelationOutOfRange(_)))));
+        assert!(matches!(scenario("[noise]\nne_correlation = -1"), Err(ScenarioError::Noise(NoiseError::InvalidCorrelation))));
+        assert!(matches!(scenario("[noise]\nv_sigma = -0.5"), Err(ScenarioError::Noise(NoiseError::InvalidSigma(_)))));
+        assert!(matches!(scenario("[noise]\nbias_time = -1"), Err(ScenarioError::Noise(NoiseError::InvalidCorrelationTime(_)))));
+        assert!(scenario("[noise]\npreset = \"gps\"\nne_correlation = 0.5").is_ok());
+    }
+
+    #[test]
+    fn rejects_invalid_sea_state() {
+        let sinusoids = "[sea_state]\nkind = \"sinusoids\"\nheave_amplitude = 1\nheave_period = 8\nroll_amplitude = 2\npitch_amplitude = 1\npitch_period = 6\nroll_period = ";
+        assert!(matches!(scenario(&format!("{}0", sinusoids)), Err(ScenarioError::SeaState(SeaStateError::InvalidPeriod(_)))));
+        assert!(scenario(&format!("{}7", sinusoids)).is_ok());
+        let jonswap = "[sea_state]\nkind = \"jonswap\"\nwave_height = 2\nwave_direction = 30\nwave_period = -3";
+        assert!(matches!(scenario(jonswap), Err(ScenarioError::SeaState(_))));
+    }
+
+    #[test]
+    fn rejects_negative_latency() {
+        assert!(matches!(scenario("[latency]\nfixed = -0.1"), Err(ScenarioError::Latency(LatencyError::NegativeFixed(_)))));
+        assert!(matches!(scenario("[latency]\njitter = -0.1"), Err(ScenarioError::Latency(LatencyError::NegativeJitter(_)))));
+    }
+
+    #[test]
+    fn rejects_non_positive_reanchor_distance() {
+        assert!(matches!(scenario("[reference]\nreanchor_distance = 0"), Err(ScenarioError::Invalid(_))));
+        assert!(scenario("[reference]\nreanchor_distance = 500").is_ok());
+    }
+
+    #[test]
+    fn rejects_non_positive_route_speeds() {
+        let route = |waypoints: &str, looping: bool| {
+            scenario(&format!("[trajectory]\nkind = \"route\"\nspeed = 5\nloop = {}\nwaypoints = [{}]", looping, waypoints))
+        };
+        assert!(route("\"ned:0,0\", \"ned:100,0\", \"ned:100,100@3\"", false).is_ok());
+        assert!(matches!(route("\"ned:0,0\", \"ned:100,0@0\", \"ned:100,100\"", false), Err(ScenarioError::Invalid(_))));
+        //The first waypoint speed only matters when looping
+        assert!(route("\"ned:0,0@-1\", \"ned:100,0\", \"ned:100,100\"", false).is_ok());
+        assert!(matches!(route("\"ned:0,0@-1\", \"ned:100,0\", \"ned:100,100\"", true), Err(ScenarioError::Invalid(_))));
+        assert!(matches!(scenario("[trajectory]\nkind = \"route\"\nspeed = 0\nwaypoints = [\"ned:0,0\", \"ned:100,0\"]"), Err(ScenarioError::Invalid(_))));
+    }
+
+    #[test]
+    fn rejects_non_finite_trajectory_values() {
+        let invalid = |text: &str| matches!(scenario(text), Err(ScenarioError::Invalid(_)));
+        assert!(invalid("[trajectory]\nkind = \"straight\"\nspeed = nan"));
+        assert!(invalid("[trajectory]\nkind = \"circle\"\nspeed = 2\nturn_rate = nan"));
+        assert!(invalid("[trajectory]\nkind = \"circle\"\nspeed = 2\nturn_rate = inf"));
+        assert!(invalid("[trajectory]\nkind = \"stationary\"\nheading = nan"));
+        assert!(invalid("[trajectory]\nkind = \"ramp\"\nfinal_speed = nan\nacceleration = 1"));
+        assert!(invalid("[trajectory]\nkind = \"figure-eight\"\nsize = 50\nperiod = nan"));
+        assert!(invalid("[[events]]\nat = 10\nkind = \"straight\"\nspeed = nan"));
+        assert!(scenario("[trajectory]\nkind = \"circle\"\nspeed = 2\nturn_rate = -3").is_ok());
+    }
+
+    #[test]
+    fn rejects_invalid_delays() {
+        assert!(matches!(scenario("delay = -1"), Err(ScenarioError::Invalid(_))));
+        assert!(matches!(scenario("delay = nan"), Err(ScenarioError::Invalid(_))));
+        assert!(matches!(scenario("delay = inf"), Err(ScenarioError::Invalid(_))));
+        assert!(scenario("delay = 0").is_ok() && scenario("delay = 2.5").is_ok());
+        assert!(matches!(scenario("[[events]]\nat = nan\nkind = \"stationary\""), Err(ScenarioError::Invalid(_))));
+        assert!(matches!(scenario("[[events]]\nat = 0\nkind = \"stationary\""), Err(ScenarioError::Invalid(_))));
+    }
+
+    #[test]
+    fn rejects_h_sigma_with_cep() {
+        assert!(matches!(scenario("[noise]\nh_sigma = 1\ncep = 1.2"), Err(ScenarioError::Invalid(_))));
+        assert!(scenario("[noise]\ncep = 1.2").is_ok() && scenario("[noise]\nh_sigma = 1").is_ok());
+    }
+
+    /// Geoid 30 m above the ellipsoid everywhere
+    fn flat_geoid() -> Geoid {
+        let mut pgm = b"P5\n# Offset 0\n# Scale 1\n4 3\n65535\n".to_vec();
+        pgm.extend(std::iter::repeat_n(30_u16.to_be_bytes(), 12).flatten());
+        Geoid::from_pgm(&pgm, Interpolation::Bilinear).unwrap()
+    }
+
+    fn target(text: &str) -> TargetConfig {
+        toml::from_str(text).unwrap()
+    }
+
+    #[test]
+    fn start_ned() {
+        let reference = Reference::new(LLA::from_degs(Degrees(45.), Degrees(-73.), 10.));
+        let start = target("latitude = 45.001\nlongitude = -73").start_ned(&reference, None).unwrap();
+        let expected = reference.lla_to_tangent(LLA::from_degs(Degrees(45.001), Degrees(-73.), 10.));
+        assert_eq!(start, expected);
+        assert!((start.north() as f64 - Earth::meters_per_degree_latitude(Degrees(45.0005)) * 0.001).abs() < 0.01, "{:?}", start);
+        assert_eq!(target("north = 12.5\neast = -7\naltitude = 25").start_ned(&reference, None).unwrap(), Ned::new(12.5, -7., -15.));
+        assert_eq!(TargetConfig::default().start_ned(&reference, None).unwrap(), Ned::new(0., 0., 0.));
+    }
+
+    #[test]
+    fn start_ned_rejects_conflicting_positions() {
+        let reference = Reference::new(LLA::from_degs(Degrees(45.), Degrees(-73.), 10.));
+        let start = |text: &str| target(text).start_ned(&reference, None);
+        assert!(matches!(start("latitude = 45.001\nlongitude = -73\nnorth = 5"), Err(ScenarioError::Invalid(_))));
+        assert!(matches!(start("latitude = 45.001"), Err(ScenarioError::Invalid(_))));
+        assert!(matches!(start("latitude = 95\nlongitude = -73"), Err(ScenarioError::Invalid(_))));
+    }
+
+    #[test]
+    fn start_ned_msl_needs_a_geoid() {
+        let reference = Reference::new(LLA::from_degs(Degrees(45.), Degrees(-73.), 10.));
+        let msl = target("north = 3\naltitude = 25\naltitude_datum = \"msl\"");
+        assert!(matches!(msl.start_ned(&reference, None), Err(ScenarioError::Invalid(_))));
+        assert_eq!(msl.start_ned(&reference, Some(&flat_geoid())).unwrap(), Ned::new(3., 0., -45.));
+        let absolute = target("latitude = 45\nlongitude = -73\naltitude = 25\naltitude_datum = \"msl\"");
+        assert!((absolute.start_ned(&reference, Some(&flat_geoid())).unwrap().down() + 45.).abs() < 1e-3);
+        //Ellipsoid altitudes do not need it
+        assert!(target("altitude = 25").start_ned(&reference, None).is_ok());
+    }
+
+    #[test]
+    fn rejects_invalid_targets() {
+        let invalid = |text: &str| matches!(scenario(text), Err(ScenarioError::Invalid(_)));
+        assert!(invalid("[target]\nlatitude = 45"));
+        assert!(invalid("[target]\nlongitude = -73"));
+        assert!(invalid("[target]\nlatitude = 91\nlongitude = -73"));
+        assert!(invalid("[target]\nlatitude = 45\nlongitude = -73\neast = 10"));
+        assert!(invalid("[target]\naltitude = 12\naltitude_datum = \"msl\""));
+        assert!(invalid("[target]\nnorth = 10\n\n[trajectory]\nkind = \"route\"\nspeed = 5\nwaypoints = [\"ned:0,0\", \"ned:100,0\"]"));
+        assert!(scenario("[target]\nnorth = 10\neast = -5\naltitude = 12").is_ok());
+        assert!(scenario("[target]\naltitude = 12\naltitude_datum = \"msl\"\ngeoid = \"egm96-5.pgm\"").is_ok());
+    }
+}
@@ -0,0 +1,242 @@
+//! Update scheduling on absolute deadlines.
+//!
+//! Deadlines are `interval` apart from the start rather than from the end of the previous update, so that the
+//! rate does not drift with the work time. When an update overruns its deadline the next one starts right away,
+//! and whole intervals that were missed are dropped instead of being run in a burst. The jitter is how late the
+//! updates start behind the deadlines they serve.
+
+use core::fmt;
+use std::time::Duration;
+
+use tokio::time::{Instant, sleep_until};
+
+/// Period of the rate reports
+const REPORT_PERIOD: Duration = Duration::from_secs(10);
+
+pub struct Scheduler {
+    interval: Duration,
+    next: Instant,
+    /// align deadlines on GNSS time multiples of the interval
+    phase_lock: bool,
+    window_start: Instant,
+    window_ticks: u32,
+    window_missed: u32,
+    /// sum of the squared lateness of the window ticks, secs²
+    window_lateness: f64,
+    missed: u64,
+}
+
+/// Rate achieved over a report period
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RateReport {
+    /// Hz
+    pub rate: f64,
+    /// secs
+    pub period: f64,
+    pub missed: u32,
+    pub total_missed: u64,
+    /// RMS lateness of the updates behind their deadlines, secs
+    pub jitter: f64,
+}
+
+impl fmt::Display for RateReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Achieved {:.2} Hz over {:.1} secs, {:.1} ms jitter, {} missed deadlines ({} in total)",
+            self.rate,
+            self.period,
+            self.jitter * 1000.,
+            self.missed,
+            self.total_missed
+        )
+    }
+}
+
+impl Scheduler {
+    /// The first deadline is one interval from now
+    pub fn new(interval: Duration, phase_lock: bool) -> Self {
+        let now = Instant::now();
+        Self {
+            interval,
+            next: now + interval,
+            phase_lock,
+            window_start: now,
+            window_ticks: 0,
+            window_missed: 0,
+            window_lateness: 0.,
+            missed: 0,
+        }
+    }
+
+    /// Moves the next deadline to the nearest GNSS time multiple of the interval, `gnss_secs` having been read
+    /// from the device at `read_at`. Does nothing unless phase locking. The device clock being read over the
+    /// network, the deadlines lag GNSS time by the telemetry latency.
+    pub fn lock_phase(&mut self, gnss_secs: f64, read_at: Instant) {
+        if !self.phase_lock {
+            return;
+        }
+        let interval = self.interval.as_secs_f64();
+        let next_gnss = if self.next >= read_at {
+            gnss_secs + (self.next - read_at).as_secs_f64()
+        } else {
+            gnss_secs - (read_at - self.next).as_secs_f64()
+        };
+        let correction = (next_gnss / interval).round() * interval - next_gnss;
+        if correction >= 0. {
+            self.next += Duration::from_secs_f64(correction);
+        } else {
+            self.next -= Duration::from_secs_f64(-correction);
+        }
+    }
+
+    /// Waits for the next deadline
+    pub async fn wait(&mut self) {
+        let now = Instant::now();
+        if now > self.next {
+            let skipped = ((now - self.next).as_nanos() / self.interval.as_nanos()) as u32;
+            self.window_missed += 1 + skipped;
+            self.missed += 1 + skipped as u64;
+            self.next += self.interval * skipped;
+        } else {
+            sleep_until(self.next).await;
+        }
+        self.window_lateness += Instant::now().saturating_duration_since(self.next).as_secs_f64().powi(2);
+        self.next += self.interval;
+        self.window_ticks += 1;
+    }
+
+    /// Rate achieved since the last report, once per report period
+    pub fn report(&mut self) -> Option<RateReport> {
+        let period = self.window_start.elapsed();
+        if period < REPORT_PERIOD {
+            return None;
+        }
+        let report = RateReport {
+            rate: self.window_ticks as f64 / period.as_secs_f64(),
+            period: period.as_secs_f64(),
+            missed: self.window_missed,
+            total_missed: self.missed,
+            jitter: if self.window_ticks == 0 { 0. } else { (self.window_lateness / self.window_ticks as f64).sqrt() },
+        };
+        self.window_start = Instant::now();
+        self.window_ticks = 0;
+        self.window_missed = 0;
+        self.window_lateness = 0.;
+        Some(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INTERVAL: Duration = Duration::from_millis(100);
+
+    fn assert_close(actual: Duration, expected: Duration) {
+        assert!(actual.abs_diff(expected) < Duration::from_micros(1), "{:?} instead of {:?}", actual, expected);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn absolute_deadlines_do_not_drift() {
+        let start = Instant::now();
+        let mut scheduler = Scheduler::new(INTERVAL, false);
+        for tick in 1..=1000 {
+            scheduler.wait().await;
+            assert_eq!(Instant::now() - start, INTERVAL * tick);
+            //Work shorter than the interval does not push the next deadline
+            sleep_until(Instant::now() + Duration::from_millis(37)).await;
+        }
+        assert_eq!(scheduler.missed, 0);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn counts_missed_deadlines() {
+        let start = Instant::now();
+        let mut scheduler = Scheduler::new(INTERVAL, false);
+        scheduler.wait().await;
+        //Overrunning the 200, 300 and 400 ms deadlines
+        tokio::time::advance(Duration::from_millis(350)).await;
+        scheduler.wait().await;
+        assert_eq!(Instant::now() - start, Duration::from_millis(450));
+        assert_eq!(scheduler.missed, 3);
+        //Late by less than an interval: only that deadline is missed
+        tokio::time::advance(Duration::from_millis(120)).await;
+        scheduler.wait().await;
+        assert_eq!(scheduler.missed, 4);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn does_not_catch_up_in_a_burst() {
+        let start = Instant::now();
+        let mut scheduler = Scheduler::new(INTERVAL, false);
+        scheduler.wait().await;
+        tokio::time::advance(Duration::from_secs(2)).await;
+        let mut ticks = Vec::new();
+        for _ in 0..4 {
+            scheduler.wait().await;
+            ticks.push((Instant::now() - start).as_millis());
+        }
+        //The stalled update runs right away, then the original grid resumes
+        assert_eq!(ticks, [2100, 2200, 2300, 2400]);
+        tokio::time::advance(Duration::from_millis(1050)).await;
+        scheduler.wait().await;
+        scheduler.wait().await;
+        assert_eq!(Instant::now() - start, Duration::from_millis(3500));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn phase_lock_rounds_to_the_nearest_boundary() {
+        let interval = Duration::from_millis(200);
+        let locked = |gnss_secs: f64, read_after: Duration| async move {
+            let start = Instant::now();
+            let mut scheduler = Scheduler::new(interval, true);
+            tokio::time::advance(read_after).await;
+            scheduler.lock_phase(gnss_secs, Instant::now());
+            scheduler.wait().await;
+            Instant::now() - start
+        };
+        //Next deadline at GNSS 1000.25 s moves back to 1000.2 s
+        assert_close(locked(1000.05, Duration::ZERO).await, Duration::from_millis(150));
+        //At 1000.33 s it moves forward to 1000.4 s
+        assert_close(locked(1000.13, Duration::ZERO).await, Duration::from_millis(270));
+        //Read after the deadline passed: GNSS 1999.93 s at the deadline, moved to 2000 s
+        assert_close(locked(2000.03, Duration::from_millis(300)).await, Duration::from_millis(300));
+        //Later deadlines stay on the boundaries
+        let start = Instant::now();
+        let mut scheduler = Scheduler::new(interval, true);
+        scheduler.lock_phase(1000.05, start);
+        for tick in 0..10 {
+            scheduler.wait().await;
+            assert_close(Instant::now() - start, Duration::from_millis(150) + interval * tick);
+        }
+        let mut unlocked = Scheduler::new(interval, false);
+        unlocked.lock_phase(1000.05, start + interval * 10);
+        unlocked.wait().await;
+        assert_close(Instant::now() - start, Duration::from_millis(1950) + interval);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn reports_rate_and_jitter() {
+        let mut scheduler = Scheduler::new(INTERVAL, false);
+        for _ in 0..99 {
+            scheduler.wait().await;
+            assert_eq!(scheduler.report(), None);
+        }
+        scheduler.wait().await;
+        let report = scheduler.report().unwrap();
+        assert_eq!(report, RateReport { rate: 10., period: 10., missed: 0, total_missed: 0, jitter: 0. });
+        //Four updates overrun by 130 ms, the next ones starting 30 ms late
+        for tick in 0..100 {
+            if tick % 25 == 5 {
+                tokio::time::advance(Duration::from_millis(130)).await;
+            }
+            scheduler.wait().await;
+        }
+        let report = scheduler.report().unwrap();
+        assert_eq!((report.missed, report.total_missed), (4, 4));
+        assert!((report.period - 10.).abs() < 1e-9 && (report.rate - 10.).abs() < 1e-9, "{:?}", report);
+        assert!((report.jitter - (4. * 0.03_f64.powi(2) / 100.).sqrt()).abs() < 1e-9, "{:?}", report);
+        assert_eq!(scheduler.report(), None);
+    }
+}
@@ -0,0 +1,189 @@
+//! Deck motion of a ship in waves.
+//!
+//! Motion is a sum of wave components, each moving heave, roll and pitch at its frequency with roll and
+//! pitch following the wave slope (a quarter period behind heave), so that the three stay coherent.
+
+use core::f64::consts::TAU;
+
+use rand::Rng;
+
+use crate::prelude::*;
+
+#[derive(thiserror::Error, Debug, Clone, Copy, PartialEq)]
+pub enum SeaStateError {
+    #[error("Wave period must be positive, got {0} s")]
+    InvalidPeriod(f64),
+}
+
+/// `period` if it is a valid wave period (seconds)
+pub fn check_period(period: f64) -> Result<f64, SeaStateError> {
+    if period > 0. && period.is_finite() { Ok(period) } else { Err(SeaStateError::InvalidPeriod(period)) }
+}
+
+/// Components of a JONSWAP synthesis
+const JONSWAP_COMPONENTS: usize = 50;
+/// Peak enhancement factor of the mean JONSWAP spectrum
+const JONSWAP_GAMMA: f64 = 3.3;
+
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub struct DeckMotion {
+    /// meters, up
+    pub heave: f64,
+    /// m/s, up
+    pub heave_rate: f64,
+    pub roll: Radians,
+    pub pitch: Radians,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Wave {
+    /// rad/s
+    frequency: f64,
+    phase: f64,
+    /// meters
+    heave: f64,
+    /// radians
+    roll: f64,
+    /// radians
+    pitch: f64,
+}
+
+/// Amplitude and period of a sinusoidal motion
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Oscillation {
+    pub amplitude: f64,
+    /// seconds
+    pub period: f64,
+}
+
+#[derive(Clone, Debug, PartialEq, Default)]
+pub struct SeaState {
+    waves: Vec<Wave>,
+}
+
+impl SeaState {
+    /// One sinusoid per axis: heave in meters, roll and pitch amplitudes in radians
+    pub fn sinusoids(heave: Oscillation, roll: Oscillation, pitch: Oscillation) -> Result<Self, SeaStateError> {
+        for oscillation in [heave, roll, pitch] {
+            check_period(oscillation.period)?;
+        }
+        let wave = |oscillation: Oscillation| Wave {
+            frequency: TAU / oscillation.period,
+            phase: 0.,
+            heave: 0.,
+            roll: 0.,
+            pitch: 0.,
+        };
+        Ok(Self {
+            waves: vec![
+                Wave {
+                    heave: heave.amplitude,
+                    ..wave(heave)
+                },
+                Wave {
+                    roll: roll.amplitude,
+                    ..wave(roll)
+                },
+                Wave {
+                    pitch: pitch.amplitude,
+                    ..wave(pitch)
+                },
+            ],
+        })
+    }
+
+    /// Random-phase synthesis of a JONSWAP spectrum of significant wave height `significant_height` (meters) and
+    /// peak period `peak_period` (seconds), for deep water waves coming from `direction` relative to the bow.
+    /// The deck follows the water surface.
+    pub fn jonswap(significant_height: f64, peak_period: f64, direction: Radians, rng: &mut impl Rng) -> Result<Self, SeaStateError> {
+        let peak = TAU / check_period(peak_period)?;
+        let spectrum = |omega: f64| {
+            let sigma = if omega <= peak { 0.07 } else { 0.09 };
+            let r = (-(omega - peak).powi(2) / (2. * sigma * sigma * peak * peak)).exp();
+            omega.powi(-5) * (-1.25 * (peak / omega).powi(4)).exp() * JONSWAP_GAMMA.powf(r)
+        };
+
+        let (low, high) = (0.5 * peak, 3. * peak);
+        let step = (high - low) / JONSWAP_COMPONENTS as f64;
+        //Frequencies are jittered within their bin so that the motion does not repeat
+        let frequencies: Vec<f64> = (0..JONSWAP_COMPONENTS)
+            .map(|i| low + (i as f64 + rng.random_range(0_f64..1_f64)) * step)
+            .collect();
+        //Scaled so that 4·√m0 is the significant wave height
+        let m0: f64 = frequencies.iter().map(|&omega| spectrum(omega) * step).sum();
+        let scale = (significant_height / 4.).powi(2) / m0;
+
+        let waves = frequencies
+            .into_iter()
+            .map(|frequency| {
+                let amplitude = (2. * scale * spectrum(frequency) * step).sqrt();
+                let slope = frequency * frequency / Earth::GRAVITY as f64 * amplitude;
+                Wave {
+                    frequency,
+                    phase: rng.random_range(0_f64..TAU),
+                    heave: amplitude,
+                    roll: slope * direction.sin(),
+                    pitch: slope * direction.cos(),
+                }
+            })
+            .collect();
+        Ok(Self { waves })
+    }
+
+    pub fn motion(&self, time: f64) -> DeckMotion {
+        self.waves.iter().fold(DeckMotion::default(), |motion, wave| {
+            let (sin, cos) = (wave.frequency * time + wave.phase).sin_cos();
+            DeckMotion {
+                heave: motion.heave + wave.heave * cos,
+                heave_rate: motion.heave_rate - wave.heave * wave.frequency * sin,
+                roll: motion.roll + Radians(wave.roll * sin),
+                pitch: motion.pitch + Radians(wave.pitch * sin),
+            }
+        })
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    fn oscillation(amplitude: f64, period: f64) -> Oscillation {
+        Oscillation { amplitude, period }
+    }
+
+    #[test]
+    fn roll_and_pitch_lag_heave_by_a_quarter_period() {
+        let sea = SeaState::sinusoids(oscillation(2., 8.), oscillation(0.1, 8.), oscillation(0.05, 8.)).unwrap();
+        let crest = sea.motion(0.);
+        assert!((crest.heave - 2.).abs() < 1e-12 && crest.roll.0.abs() < 1e-12 && crest.pitch.0.abs() < 1e-12);
+        let later = sea.motion(2.);
+        assert!(later.heave.abs() < 1e-12, "{:?}", later);
+        assert!((later.roll.0 - 0.1).abs() < 1e-12 && (later.pitch.0 - 0.05).abs() < 1e-12, "{:?}", later);
+        //Heave rate is the derivative of heave
+        let dt = 1e-4;
+        let rate = (sea.motion(1. + dt).heave - sea.motion(1. - dt).heave) / (2. * dt);
+        assert!((sea.motion(1.).heave_rate - rate).abs() < 1e-6);
+    }
+
+    #[test]
+    fn rejects_non_positive_periods() {
+        for period in [0., -5., f64::NAN] {
+            assert!(SeaState::sinusoids(oscillation(1., 8.), oscillation(0.1, period), oscillation(0.1, 8.)).is_err());
+            assert!(SeaState::jonswap(2., period, Radians(0.), &mut ChaCha8Rng::seed_from_u64(1)).is_err());
+        }
+    }
+
+    #[test]
+    fn jonswap_significant_height() {
+        let sea = SeaState::jonswap(3., 9., Degrees(30.).to_radians(), &mut ChaCha8Rng::seed_from_u64(7)).unwrap();
+        assert_eq!(sea.waves.len(), JONSWAP_COMPONENTS);
+        //4·√m0 with m0 the variance of the heave, half the sum of the squared amplitudes
+        let m0: f64 = sea.waves.iter().map(|w| w.heave * w.heave / 2.).sum();
+        assert!((4. * m0.sqrt() - 3.).abs() < 1e-9);
+        //Waves from 30° off the bow tilt the deck more in pitch than in roll
+        assert!(sea.waves.iter().all(|w| (w.roll / w.pitch - Degrees(30.).to_radians().0.tan()).abs() < 1e-9));
+    }
+}
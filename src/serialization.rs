@@ -0,0 +1,177 @@
+//! Serde representations of the coordinate types.
+//!
+//! `LLA` serializes as `{ latitude, longitude, altitude }` in radians by default, and `Reference` as its `LLA`
+//! only, its cached terms being rebuilt on deserialization. Use `#[serde(with = "degrees")]` on a field of
+//! either type to store degrees instead, or `#[serde(with = "radians")]` to be explicit.
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::prelude::*;
+
+#[derive(Serialize, Deserialize)]
+struct RawLLA {
+    latitude: f64,
+    longitude: f64,
+    altitude: f32,
+}
+
+fn validated<E: serde::de::Error>(latitude: Radians, longitude: Radians, altitude: f32) -> Result<LLA, E> {
+    LLA::new(latitude, longitude, altitude).map_err(E::custom)
+}
+
+impl Serialize for LLA {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        radians::serialize(self, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for LLA {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        radians::deserialize(deserializer)
+    }
+}
+
+impl Serialize for Reference {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.lla.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Reference {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(Reference::new(LLA::deserialize(deserializer)?))
+    }
+}
+
+impl From<Reference> for LLA {
+    fn from(reference: Reference) -> Self {
+        reference.lla
+    }
+}
+
+impl From<LLA> for Reference {
+    fn from(lla: LLA) -> Self {
+        Reference::new(lla)
+    }
+}
+
+/// `{ latitude, longitude, altitude }` with angles in radians
+pub mod radians {
+    use super::*;
+
+    pub fn serialize<T: Clone + Into<LLA>, S: Serializer>(value: &T, serializer: S) -> Result<S::Ok, S::Error> {
+        let lla: LLA = value.clone().into();
+        RawLLA {
+            latitude: lla.lat().0,
+            longitude: lla.lon().0,
+            altitude: lla.altitude,
+        }
+        .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, T: From<LLA>, D: Deserializer<'de>>(deserializer: D) -> Result<T, D::Error> {
+        let raw = RawLLA::deserialize(deserializer)?;
+        Ok(validated(Radians(raw.latitude), Radians(raw.longitude), raw.altitude)?.into())
+    }
+}
+
+/// `{ latitude, longitude, altitude }` with angles in degrees
+pub mod degrees {
+    use super::*;
+
+    pub fn serialize<T: Clone + Into<LLA>, S: Serializer>(value: &T, serializer: S) -> Result<S::Ok, S::Error> {
+        let lla: LLA = value.clone().into();
+        RawLLA {
+            latitude: lla.lat().to_degrees().0,
+            longitude: lla.lon().to_degrees().0,
+            altitude: lla.altitude,
+        }
+        .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, T: From<LLA>, D: Deserializer<'de>>(deserializer: D) -> Result<T, D::Error> {
+        let raw = RawLLA::deserialize(deserializer)?;
+        Ok(validated(Degrees(raw.latitude).into(), Degrees(raw.longitude).into(), raw.altitude)?.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[derive(Serialize, Deserialize)]
+    struct InDegrees {
+        #[serde(with = "degrees")]
+        lla: LLA,
+        #[serde(with = "degrees")]
+        reference: Reference,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct InRadians {
+        #[serde(with = "radians")]
+        lla: LLA,
+    }
+
+    fn lla() -> LLA {
+        LLA::from_degs(Degrees(-33.86), Degrees(151.21), 120.5)
+    }
+
+    fn assert_lla(actual: LLA, expected: LLA) {
+        assert!((actual.lat().0 - expected.lat().0).abs() < 1e-15 && (actual.lon().0 - expected.lon().0).abs() < 1e-15);
+        assert_eq!(actual.altitude, expected.altitude);
+    }
+
+    #[test]
+    fn lla_defaults_to_radians() {
+        let value = serde_json::to_value(lla()).unwrap();
+        assert_eq!(value, json!({ "latitude": lla().lat().0, "longitude": lla().lon().0, "altitude": 120.5 }));
+        assert_eq!(serde_json::to_value(InRadians { lla: lla() }).unwrap(), json!({ "lla": value }));
+    }
+
+    #[test]
+    fn degrees_representation() {
+        let value = serde_json::to_value(InDegrees { lla: lla(), reference: Reference::new(lla()) }).unwrap();
+        let degrees = &value["lla"];
+        assert!((degrees["latitude"].as_f64().unwrap() + 33.86).abs() < 1e-12);
+        assert!((degrees["longitude"].as_f64().unwrap() - 151.21).abs() < 1e-12);
+        assert_eq!(degrees["altitude"], json!(120.5));
+        assert_eq!(value["reference"], value["lla"]);
+    }
+
+    #[test]
+    fn round_trips() {
+        let text = serde_json::to_string(&lla()).unwrap();
+        assert_lla(serde_json::from_str(&text).unwrap(), lla());
+        let text = serde_json::to_string(&InRadians { lla: lla() }).unwrap();
+        assert_lla(serde_json::from_str::<InRadians>(&text).unwrap().lla, lla());
+        let text = serde_json::to_string(&InDegrees { lla: lla(), reference: Reference::new(lla()) }).unwrap();
+        let back: InDegrees = serde_json::from_str(&text).unwrap();
+        assert_lla(back.lla, lla());
+        assert_lla(back.reference.lla, lla());
+    }
+
+    #[test]
+    fn rejects_invalid_latitude() {
+        assert!(serde_json::from_value::<LLA>(json!({ "latitude": 1.6, "longitude": 0., "altitude": 0. })).is_err());
+        assert!(serde_json::from_value::<Reference>(json!({ "latitude": f64::NAN, "longitude": 0., "altitude": 0. })).is_err());
+        let degrees = json!({ "latitude": 90.5, "longitude": 0., "altitude": 0. });
+        assert!(serde_json::from_value::<InDegrees>(json!({ "lla": degrees, "reference": degrees })).is_err());
+        //Radians up to π/2 are fine
+        assert!(serde_json::from_value::<LLA>(json!({ "latitude": 1.5, "longitude": 0., "altitude": 0. })).is_ok());
+    }
+
+    #[test]
+    fn reference_rebuilds_cached_terms() {
+        let reference: Reference = serde_json::from_value(serde_json::to_value(lla()).unwrap()).unwrap();
+        let expected = Reference::new(lla());
+        let point = LLA::from_degs(Degrees(-33.85), Degrees(151.22), 80.);
+        assert!((reference.lla_to_tangent(point) - expected.lla_to_tangent(point)).as_vector().norm() < 1e-9);
+        assert!((reference.ecef_to_ned_rotation() - expected.ecef_to_ned_rotation()).norm() < 1e-15);
+        assert_eq!([reference.ecef.x, reference.ecef.y, reference.ecef.z], [expected.ecef.x, expected.ecef.y, expected.ecef.z]);
+        let north = reference.lla_to_tangent(LLA::from_degs(Degrees(-33.85), Degrees(151.21), 120.5));
+        assert!((north.as_vector().x - 1109.).abs() < 1., "{:?}", north);
+    }
+}
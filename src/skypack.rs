@@ -8,17 +8,30 @@ use tokio::net::UdpSocket;
 use tokio::sync::oneshot;
 use tokio::time::timeout;
 use std::future::Future;
-use chrono::Utc;
-use serde_json;
-use serde_json::json;
-use crate::LLA;
+use crate::{Ned, LLA};
 
 #[derive(Serialize, Debug)]
-struct RequestPacket {
+struct RequestPacket<T> {
     req: u32,
     id: u64,
     #[serde(skip_serializing_if = "Option::is_none")]
-    data: Option<serde_json::Value>,
+    data: Option<T>,
+}
+
+/// Typed rather than built with `json!`, which cannot hold non-finite numbers
+#[derive(Serialize, Debug)]
+struct LandingZoneItem {
+    id: u32,
+    frame: &'static str,
+    pos: [f64; 3],
+    vel: [f64; 3],
+    rpy: [f64; 3],
+    ts: f64,
+}
+
+#[derive(Serialize, Debug)]
+struct LandingZones {
+    items: [LandingZoneItem; 1],
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -124,7 +137,7 @@ impl Skypack {
 
     /// The core logic handles ID generation, retry loops, and timeouts.
     /// It returns a Future that resolves when the whole process is done.
-    async fn perform_request(&self, req: u32, data: Option<serde_json::Value>) -> Result<ResponsePacket, DeviceError> {
+    async fn perform_request<T: Serialize>(&self, req: u32, data: Option<T>) -> Result<ResponsePacket, DeviceError> {
         // 1. Generate ID (increments automatically)
         let id = self.next_id.fetch_add(1, Ordering::SeqCst);
 
@@ -174,7 +187,7 @@ impl Skypack {
     pub fn get_telemetry(self: &Arc<Self>) -> RequestHandle {
         let self_clone = self.clone();
         let handle = tokio::spawn(async move {
-            self_clone.perform_request(9, None).await
+            self_clone.perform_request(9, None::<()>).await
         });
 
         RequestHandle { inner: handle }
@@ -186,20 +199,23 @@ impl Skypack {
         // Handle::block_on is the standard way to bridge sync -> async
         tokio::task::block_in_place(|| {
             tokio::runtime::Handle::current().block_on(async {
-                self.perform_request(9, None).await
+                self.perform_request(9, None::<()>).await
             })
         })
     }
 
-    pub fn set_precision_landing_zone(self: &Arc<Self>, lla: LLA, vel: nalgebra::Vector3<f32>, timestamp: f64) -> RequestHandle {
-        let data = json!({ "items": [{
-            "id": 1,
-            "frame": "lla".to_owned(),
-            "pos": [lla.latitude.to_degrees(), lla.longitude.to_degrees(), lla.altitude],
-            "vel":  [vel[0], vel[1], vel[2]],
-            "rpy": [0., 0., 0.],
-            "ts": timestamp
-        }] });
+    /// `rpy` is sent as given, see [`RpyUnit`](crate::scenario::RpyUnit) for its unit
+    pub fn set_precision_landing_zone(self: &Arc<Self>, lla: LLA, vel: Ned<f32>, rpy: [f64; 3], timestamp: f64) -> RequestHandle {
+        let data = LandingZones {
+            items: [LandingZoneItem {
+                id: 1,
+                frame: "lla",
+                pos: [lla.lat().to_degrees().0, lla.lon().to_degrees().0, lla.altitude as f64],
+                vel: [vel.north() as f64, vel.east() as f64, vel.down() as f64],
+                rpy,
+                ts: timestamp,
+            }],
+        };
 
         let self_clone = self.clone();
         let handle = tokio::spawn(async move {
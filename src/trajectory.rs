@@ -0,0 +1,290 @@
+//! Target motion models.
+//!
+//! A trajectory gives the target state at a time in seconds since its start (GNSS time minus the start time,
+//! negative before it). Positions are NED displacements from the start position and velocities are local
+//! NED velocities.
+
+use core::f64::consts::TAU;
+
+use crate::prelude::*;
+
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub struct TrajectoryState {
+    pub position: Ned<f32>,
+    pub velocity: Ned<f32>,
+    /// course over ground, from true north
+    pub heading: Radians,
+}
+
+pub trait Trajectory: Send {
+    fn state(&self, time: f64) -> TrajectoryState;
+}
+
+impl<T: Trajectory + ?Sized> Trajectory for Box<T> {
+    fn state(&self, time: f64) -> TrajectoryState {
+        (**self).state(time)
+    }
+}
+
+/// Horizontal NED vector of `length` along `heading`
+fn along(heading: Radians, length: f64) -> Ned<f32> {
+    Ned::new((heading.cos() * length) as f32, (heading.sin() * length) as f32, 0.)
+}
+
+/// Stays at the start position
+pub struct Stationary {
+    pub heading: Radians,
+}
+
+impl Trajectory for Stationary {
+    fn state(&self, _time: f64) -> TrajectoryState {
+        TrajectoryState {
+            heading: self.heading,
+            ..Default::default()
+        }
+    }
+}
+
+/// Straight line at a constant speed
+pub struct ConstantVelocity {
+    pub heading: Radians,
+    /// m/s
+    pub speed: f64,
+}
+
+impl Trajectory for ConstantVelocity {
+    fn state(&self, time: f64) -> TrajectoryState {
+        TrajectoryState {
+            position: along(self.heading, self.speed * time),
+            velocity: along(self.heading, self.speed),
+            heading: self.heading,
+        }
+    }
+}
+
+/// Circle at a constant speed and turn rate
+pub struct ConstantTurn {
+    /// heading at the start
+    pub heading: Radians,
+    /// m/s
+    pub speed: f64,
+    /// rad/s, positive turning right (clockwise seen from above)
+    pub turn_rate: f64,
+}
+
+impl Trajectory for ConstantTurn {
+    fn state(&self, time: f64) -> TrajectoryState {
+        if self.turn_rate == 0. {
+            return ConstantVelocity {
+                heading: self.heading,
+                speed: self.speed,
+            }
+            .state(time);
+        }
+        let heading = self.heading + Radians(self.turn_rate * time);
+        let radius = self.speed / self.turn_rate;
+        TrajectoryState {
+            position: Ned::new(
+                (radius * (heading.sin() - self.heading.sin())) as f32,
+                (radius * (self.heading.cos() - heading.cos())) as f32,
+                0.,
+            ),
+            velocity: along(heading, self.speed),
+            heading: heading.wrap_two_pi(),
+        }
+    }
+}
+
+/// Figure-eight (lemniscate of Gerono) whose long axis follows `heading`, crossing itself at the start position
+pub struct FigureEight {
+    pub heading: Radians,
+    /// half the length of the long axis (meters)
+    pub size: f64,
+    /// duration of a full eight (seconds)
+    pub period: f64,
+}
+
+impl Trajectory for FigureEight {
+    fn state(&self, time: f64) -> TrajectoryState {
+        let omega = TAU / self.period;
+        let phase = omega * time;
+        //Along and across the long axis
+        let (along_axis, across_axis) = (self.size * phase.sin(), self.size / 2. * (2. * phase).sin());
+        let (along_rate, across_rate) = (self.size * omega * phase.cos(), self.size * omega * (2. * phase).cos());
+        let (sin, cos) = (self.heading.sin(), self.heading.cos());
+        let rotate = |a: f64, c: f64| Ned::new((a * cos - c * sin) as f32, (a * sin + c * cos) as f32, 0.);
+        let velocity = rotate(along_rate, across_rate);
+        TrajectoryState {
+            position: rotate(along_axis, across_axis),
+            velocity,
+            heading: Radians((velocity.east() as f64).atan2(velocity.north() as f64)).wrap_two_pi(),
+        }
+    }
+}
+
+/// Straight line, accelerating or decelerating from `initial_speed` to `final_speed`, then holding it
+pub struct Ramp {
+    pub heading: Radians,
+    /// m/s
+    pub initial_speed: f64,
+    /// m/s
+    pub final_speed: f64,
+    /// m/s², magnitude
+    pub acceleration: f64,
+}
+
+impl Trajectory for Ramp {
+    fn state(&self, time: f64) -> TrajectoryState {
+        let acceleration = self.acceleration.abs().copysign(self.final_speed - self.initial_speed);
+        let ramp_time = if acceleration == 0. { 0. } else { (self.final_speed - self.initial_speed) / acceleration };
+        let (distance, speed) = if time <= 0. {
+            (self.initial_speed * time, self.initial_speed)
+        } else if time < ramp_time {
+            (self.initial_speed * time + acceleration * time * time / 2., self.initial_speed + acceleration * time)
+        } else {
+            let ramp_distance = (self.initial_speed + self.final_speed) / 2. * ramp_time;
+            (ramp_distance + self.final_speed * (time - ramp_time), self.final_speed)
+        };
+        TrajectoryState {
+            position: along(self.heading, distance),
+            velocity: along(self.heading, speed),
+            heading: self.heading,
+        }
+    }
+}
+
+/// Trajectories played one after the other, each starting where the previous one ended.
+/// The last segment lasts forever, and the first one also applies before the start.
+#[derive(Default)]
+pub struct Piecewise {
+    /// (duration in seconds, trajectory)
+    segments: Vec<(f64, Box<dyn Trajectory>)>,
+}
+
+impl Piecewise {
+    pub fn new() -> Self {
+        Self { segments: Vec::new() }
+    }
+
+    pub fn then(mut self, duration: f64, trajectory: impl Trajectory + 'static) -> Self {
+        self.segments.push((duration, Box::new(trajectory)));
+        self
+    }
+}
+
+impl Trajectory for Piecewise {
+    fn state(&self, time: f64) -> TrajectoryState {
+        let mut start_position = Ned::default();
+        let mut start_time = 0.;
+        for (index, (duration, segment)) in self.segments.iter().enumerate() {
+            let is_last = index + 1 == self.segments.len();
+            let local_time = time - start_time;
+            if is_last || local_time < *duration {
+                let state = segment.state(local_time);
+                return TrajectoryState {
+                    position: start_position + state.position - segment.state(0.).position,
+                    ..state
+                };
+            }
+            start_position += segment.state(*duration).position - segment.state(0.).position;
+            start_time += duration;
+        }
+        TrajectoryState::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_ned(actual: Ned<f32>, expected: Ned<f32>, tolerance: f32) {
+        assert!((actual - expected).as_vector().norm() <= tolerance, "{:?} instead of {:?}", actual, expected);
+    }
+
+    /// Velocity matches the central difference of the position, and heading the velocity direction
+    fn assert_consistent(trajectory: &dyn Trajectory, times: impl IntoIterator<Item = f64>) {
+        for time in times {
+            let state = trajectory.state(time);
+            let dt = 1e-3;
+            let difference = (trajectory.state(time + dt).position - trajectory.state(time - dt).position) / (2. * dt) as f32;
+            assert_ned(state.velocity, difference, 0.02);
+            if state.velocity.as_vector().norm() > 0.1 {
+                let course = Radians((state.velocity.east() as f64).atan2(state.velocity.north() as f64));
+                assert!((course - state.heading).wrap_pi().0.abs() < 1e-5, "{:?} at {}", state, time);
+            }
+        }
+    }
+
+    #[test]
+    fn constant_velocity() {
+        let trajectory = ConstantVelocity { heading: Degrees(90.).to_radians(), speed: 10. };
+        assert_ned(trajectory.state(3.).position, Ned::new(0., 30., 0.), 1e-4);
+        assert_ned(trajectory.state(-1.).position, Ned::new(0., -10., 0.), 1e-4);
+        assert_consistent(&trajectory, [0., 5.]);
+    }
+
+    #[test]
+    fn constant_turn() {
+        let speed = 20.;
+        let turn_rate = Degrees(6.).to_radians().0;
+        let trajectory = ConstantTurn { heading: Radians(0.), speed, turn_rate };
+        let radius = speed / turn_rate;
+        //Quarter turn to the right: heading east, one radius north and east of the start
+        let quarter = trajectory.state(15.);
+        assert_ned(quarter.position, Ned::new(radius as f32, radius as f32, 0.), 1e-3);
+        assert!((quarter.heading.to_degrees().0 - 90.).abs() < 1e-9);
+        //Back at the start after a full turn
+        assert_ned(trajectory.state(60.).position, Ned::default(), 1e-3);
+        //Left turns go west
+        let left = ConstantTurn { heading: Radians(0.), speed, turn_rate: -turn_rate };
+        assert_ned(left.state(15.).position, Ned::new(radius as f32, -radius as f32, 0.), 1e-3);
+        assert!((left.state(15.).heading.to_degrees().0 - 270.).abs() < 1e-9);
+        //No turn rate is a straight line
+        assert_ned(ConstantTurn { heading: Radians(0.), speed, turn_rate: 0. }.state(2.).position, Ned::new(40., 0., 0.), 1e-4);
+        assert_consistent(&trajectory, [0., 7., 40.]);
+        assert_consistent(&left, [0., 7., 40.]);
+    }
+
+    #[test]
+    fn figure_eight() {
+        let trajectory = FigureEight { heading: Degrees(30.).to_radians(), size: 100., period: 40. };
+        //Crosses itself at the start, half way and after each period
+        for time in [0., 20., 40., -40.] {
+            assert_ned(trajectory.state(time).position, Ned::default(), 1e-3);
+        }
+        //Tip of the long axis after a quarter period
+        let (sin, cos) = Degrees(30.).to_radians().0.sin_cos();
+        assert_ned(trajectory.state(10.).position, Ned::new((100. * cos) as f32, (100. * sin) as f32, 0.), 1e-3);
+        assert_consistent(&trajectory, [0., 3., 10., 27.]);
+    }
+
+    #[test]
+    fn ramp() {
+        let trajectory = Ramp { heading: Radians(0.), initial_speed: 2., final_speed: 12., acceleration: 2. };
+        //5 s of acceleration covering 35 m, then 12 m/s
+        assert_ned(trajectory.state(5.).position, Ned::new(35., 0., 0.), 1e-4);
+        assert_ned(trajectory.state(7.).position, Ned::new(59., 0., 0.), 1e-4);
+        assert_ned(trajectory.state(-1.).position, Ned::new(-2., 0., 0.), 1e-4);
+        assert!((trajectory.state(2.).velocity.north() - 6.).abs() < 1e-6);
+        assert_consistent(&trajectory, [-1., 1., 6.]);
+        //Deceleration ignores the sign of the acceleration
+        let braking = Ramp { heading: Radians(0.), initial_speed: 12., final_speed: 0., acceleration: 3. };
+        assert_ned(braking.state(10.).position, Ned::new(24., 0., 0.), 1e-4);
+        assert_eq!(braking.state(10.).velocity, Ned::default());
+    }
+
+    #[test]
+    fn piecewise_segments_chain() {
+        let east = Degrees(90.).to_radians();
+        let trajectory = Piecewise::new()
+            .then(10., ConstantVelocity { heading: Radians(0.), speed: 10. })
+            .then(15., ConstantTurn { heading: Radians(0.), speed: 10., turn_rate: Degrees(6.).to_radians().0 })
+            .then(f64::INFINITY, ConstantVelocity { heading: east, speed: 10. });
+        let radius = (10. / Degrees(6.).to_radians().0) as f32;
+        assert_ned(trajectory.state(10.).position, Ned::new(100., 0., 0.), 1e-3);
+        assert_ned(trajectory.state(25.).position, Ned::new(100. + radius, radius, 0.), 1e-3);
+        assert_ned(trajectory.state(27.).position, Ned::new(100. + radius, radius + 20., 0.), 1e-3);
+        assert_ned(trajectory.state(-2.).position, Ned::new(-20., 0., 0.), 1e-3);
+        assert_consistent(&trajectory, [5., 10.5, 24.5, 30.]);
+    }
+}
@@ -0,0 +1,253 @@
+use core::fmt;
+
+use crate::prelude::*;
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq)]
+pub enum UtmError {
+    #[error("Latitude {0} degrees is outside the UTM limits [-80, 84]")]
+    LatitudeOutOfRange(Degrees),
+    #[error("Invalid UTM zone {0}")]
+    InvalidZone(u8),
+    #[error("Invalid MGRS reference: {0}")]
+    InvalidMgrs(String),
+    #[error(transparent)]
+    Coordinate(#[from] LLAError),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Hemisphere {
+    North,
+    South,
+}
+
+/// Universal Transverse Mercator coordinate on WGS-84
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Utm {
+    /// 1 to 60
+    pub zone: u8,
+    pub hemisphere: Hemisphere,
+    /// meters, including the 500 km false easting
+    pub easting: f64,
+    /// meters, including the 10000 km false northing in the southern hemisphere
+    pub northing: f64,
+}
+
+impl fmt::Display for Utm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let hemisphere = match self.hemisphere {
+            Hemisphere::North => 'N',
+            Hemisphere::South => 'S',
+        };
+        let precision = f.precision().unwrap_or(3);
+        write!(f, "{} {} {:.*} {:.*}", self.zone, hemisphere, precision, self.easting, precision, self.northing)
+    }
+}
+
+const SCALE_FACTOR: f64 = 0.9996;
+const FALSE_EASTING: f64 = 500e3;
+const FALSE_NORTHING: f64 = 10000e3;
+
+/// Third flattening
+const N: f64 = Earth::FLATNESS / (2. - Earth::FLATNESS);
+
+/// Krüger series to sixth order in n, see Karney (2011), Transverse Mercator with an accuracy of a few nanometers
+struct KrugerSeries {
+    /// rectifying radius
+    a: f64,
+    alpha: [f64; 6],
+    beta: [f64; 6],
+}
+
+fn kruger_series() -> KrugerSeries {
+    let n = N;
+    let (n2, n3, n4, n5, n6) = (n * n, n.powi(3), n.powi(4), n.powi(5), n.powi(6));
+    KrugerSeries {
+        a: Earth::EQUATORIAL_RADIUS / (1. + n) * (1. + n2 / 4. + n4 / 64. + n6 / 256.),
+        alpha: [
+            n / 2. - 2. / 3. * n2 + 5. / 16. * n3 + 41. / 180. * n4 - 127. / 288. * n5 + 7891. / 37800. * n6,
+            13. / 48. * n2 - 3. / 5. * n3 + 557. / 1440. * n4 + 281. / 630. * n5 - 1983433. / 1935360. * n6,
+            61. / 240. * n3 - 103. / 140. * n4 + 15061. / 26880. * n5 + 167603. / 181440. * n6,
+            49561. / 161280. * n4 - 179. / 168. * n5 + 6601661. / 7257600. * n6,
+            34729. / 80640. * n5 - 3418889. / 1995840. * n6,
+            212378941. / 319334400. * n6,
+        ],
+        beta: [
+            n / 2. - 2. / 3. * n2 + 37. / 96. * n3 - 1. / 360. * n4 - 81. / 512. * n5 + 96199. / 604800. * n6,
+            1. / 48. * n2 + 1. / 15. * n3 - 437. / 1440. * n4 + 46. / 105. * n5 - 1118711. / 3870720. * n6,
+            17. / 480. * n3 - 37. / 840. * n4 - 209. / 4480. * n5 + 5569. / 90720. * n6,
+            4397. / 161280. * n4 - 11. / 504. * n5 - 830251. / 7257600. * n6,
+            4583. / 161280. * n5 - 108847. / 3991680. * n6,
+            20648693. / 638668800. * n6,
+        ],
+    }
+}
+
+/// Standard zone, including the Norway and Svalbard exceptions
+fn zone_of(latitude: Degrees, longitude: Degrees) -> u8 {
+    let (lat, lon) = (latitude.0, longitude.wrap_180().0);
+    let mut zone = ((lon + 180.) / 6.).floor() as u8 % 60 + 1;
+    if (56. ..64.).contains(&lat) && (3. ..12.).contains(&lon) {
+        zone = 32;
+    }
+    if (72. ..=84.).contains(&lat) && (0. ..42.).contains(&lon) {
+        zone = match lon {
+            l if l < 9. => 31,
+            l if l < 21. => 33,
+            l if l < 33. => 35,
+            _ => 37,
+        };
+    }
+    zone
+}
+
+fn central_meridian(zone: u8) -> Radians {
+    Degrees(zone as f64 * 6. - 183.).to_radians()
+}
+
+impl LLA {
+    /// Projects into the standard UTM zone for this position
+    pub fn to_utm(&self) -> Result<Utm, UtmError> {
+        self.to_utm_zone(zone_of(self.lat().to_degrees(), self.lon().to_degrees()))
+    }
+
+    /// Projects into a given UTM zone, e.g. to keep a whole survey in one zone
+    pub fn to_utm_zone(&self, zone: u8) -> Result<Utm, UtmError> {
+        if !(1..=60).contains(&zone) {
+            return Err(UtmError::InvalidZone(zone));
+        }
+        let latitude = self.lat().to_degrees();
+        if !(-80. ..=84.).contains(&latitude.0) {
+            return Err(UtmError::LatitudeOutOfRange(latitude));
+        }
+
+        let series = kruger_series();
+        let e = Earth::ECCENTRICITY_SQUARED.sqrt();
+        let lambda = (self.lon() - central_meridian(zone)).wrap_pi().0;
+        let (sin_lambda, cos_lambda) = lambda.sin_cos();
+
+        //Conformal latitude
+        let tau = self.lat().0.tan();
+        let sigma = (e * (e * self.lat().0.sin()).atanh()).sinh();
+        let tau_prime = tau * (1. + sigma * sigma).sqrt() - sigma * (1. + tau * tau).sqrt();
+
+        let xi_prime = tau_prime.atan2(cos_lambda);
+        let eta_prime = (sin_lambda / (tau_prime * tau_prime + cos_lambda * cos_lambda).sqrt()).asinh();
+
+        let (mut xi, mut eta) = (xi_prime, eta_prime);
+        for (j, alpha) in series.alpha.iter().enumerate() {
+            let k = 2. * (j + 1) as f64;
+            xi += alpha * (k * xi_prime).sin() * (k * eta_prime).cosh();
+            eta += alpha * (k * xi_prime).cos() * (k * eta_prime).sinh();
+        }
+
+        let hemisphere = if self.lat().0 >= 0. { Hemisphere::North } else { Hemisphere::South };
+        let false_northing = match hemisphere {
+            Hemisphere::North => 0.,
+            Hemisphere::South => FALSE_NORTHING,
+        };
+        Ok(Utm {
+            zone,
+            hemisphere,
+            easting: SCALE_FACTOR * series.a * eta + FALSE_EASTING,
+            northing: SCALE_FACTOR * series.a * xi + false_northing,
+        })
+    }
+
+    pub fn from_utm(utm: &Utm, altitude: f32) -> Result<LLA, UtmError> {
+        if !(1..=60).contains(&utm.zone) {
+            return Err(UtmError::InvalidZone(utm.zone));
+        }
+        let series = kruger_series();
+        let e = Earth::ECCENTRICITY_SQUARED.sqrt();
+        let false_northing = match utm.hemisphere {
+            Hemisphere::North => 0.,
+            Hemisphere::South => FALSE_NORTHING,
+        };
+        let xi = (utm.northing - false_northing) / (SCALE_FACTOR * series.a);
+        let eta = (utm.easting - FALSE_EASTING) / (SCALE_FACTOR * series.a);
+
+        let (mut xi_prime, mut eta_prime) = (xi, eta);
+        for (j, beta) in series.beta.iter().enumerate() {
+            let k = 2. * (j + 1) as f64;
+            xi_prime -= beta * (k * xi).sin() * (k * eta).cosh();
+            eta_prime -= beta * (k * xi).cos() * (k * eta).sinh();
+        }
+
+        let sinh_eta_prime = eta_prime.sinh();
+        let (sin_xi_prime, cos_xi_prime) = xi_prime.sin_cos();
+        let tau_prime = sin_xi_prime / (sinh_eta_prime * sinh_eta_prime + cos_xi_prime * cos_xi_prime).sqrt();
+
+        //Newton-Raphson from conformal to geodetic latitude
+        let mut tau = tau_prime;
+        for _ in 0..10 {
+            let sigma = (e * (e * tau / (1. + tau * tau).sqrt()).atanh()).sinh();
+            let tau_i = tau * (1. + sigma * sigma).sqrt() - sigma * (1. + tau * tau).sqrt();
+            let delta = (tau_prime - tau_i) / (1. + tau_i * tau_i).sqrt() * (1. + (1. - Earth::ECCENTRICITY_SQUARED) * tau * tau)
+                / ((1. - Earth::ECCENTRICITY_SQUARED) * (1. + tau * tau).sqrt());
+            tau += delta;
+            if delta.abs() < 1e-12 {
+                break;
+            }
+        }
+
+        let latitude = Radians(tau.atan());
+        let longitude = central_meridian(utm.zone) + Radians(sinh_eta_prime.atan2(cos_xi_prime));
+        Ok(LLA::new(latitude, longitude, altitude)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lla::lla;
+
+    fn assert_utm(utm: Utm, zone: u8, hemisphere: Hemisphere, easting: f64, northing: f64, tolerance: f64) {
+        assert_eq!((utm.zone, utm.hemisphere), (zone, hemisphere), "{}", utm);
+        assert!((utm.easting - easting).abs() < tolerance && (utm.northing - northing).abs() < tolerance, "{}", utm);
+    }
+
+    #[test]
+    fn published_points() {
+        //GeographicLib GeoConvert documentation
+        assert_utm(lla(33.3, 44.4, 0.).to_utm().unwrap(), 38, Hemisphere::North, 444140.54, 3684706.36, 0.005);
+        //CN Tower, 43°38′33.24″N 79°23′13.7″W
+        let cn_tower = lla(43. + 38. / 60. + 33.24 / 3600., -(79. + 23. / 60. + 13.7 / 3600.), 0.);
+        assert_utm(cn_tower.to_utm().unwrap(), 17, Hemisphere::North, 630084., 4833438., 1.);
+    }
+
+    #[test]
+    fn southern_hemisphere_mirrors_northern() {
+        //Mirroring 33.3N 44.4E about the equator and the zone 38 central meridian (45E)
+        assert_utm(lla(-33.3, 45.6, 0.).to_utm().unwrap(), 38, Hemisphere::South, 1e6 - 444140.54, 1e7 - 3684706.36, 0.005);
+    }
+
+    #[test]
+    fn round_trips() {
+        for (latitude, longitude) in [(33.3, 44.4), (-33.3, 45.6), (0., 0.), (-0.001, -179.999), (83.9, 30.), (-79.9, -70.), (45.5, -73.5)] {
+            let utm = lla(latitude, longitude, 0.).to_utm().unwrap();
+            let back = LLA::from_utm(&utm, 0.).unwrap();
+            assert!((back.lat().to_degrees().0 - latitude).abs() < 1e-9, "{} {}: {}", latitude, longitude, back);
+            assert!((back.lon().to_degrees() - Degrees(longitude)).wrap_180().0.abs() < 1e-9, "{} {}: {}", latitude, longitude, back);
+        }
+    }
+
+    #[test]
+    fn norway_and_svalbard_zones() {
+        //Zone 32V is widened west over Norway
+        assert_eq!(lla(60., 5., 0.).to_utm().unwrap().zone, 32);
+        assert_eq!(lla(60., 2., 0.).to_utm().unwrap().zone, 31);
+        assert_eq!(lla(55.9, 5., 0.).to_utm().unwrap().zone, 31);
+        //Svalbard only uses the odd zones 31X, 33X, 35X and 37X
+        for (longitude, zone) in [(8., 31), (10., 33), (20., 33), (22., 35), (32., 35), (34., 37), (41., 37), (43., 38)] {
+            assert_eq!(lla(78., longitude, 0.).to_utm().unwrap().zone, zone, "78N {}E", longitude);
+        }
+        assert_eq!(lla(71.9, 10., 0.).to_utm().unwrap().zone, 32);
+    }
+
+    #[test]
+    fn rejects_out_of_range() {
+        assert!(matches!(lla(84.5, 0., 0.).to_utm(), Err(UtmError::LatitudeOutOfRange(_))));
+        assert!(matches!(lla(-80.5, 0., 0.).to_utm(), Err(UtmError::LatitudeOutOfRange(_))));
+        assert_eq!(lla(0., 0., 0.).to_utm_zone(61), Err(UtmError::InvalidZone(61)));
+    }
+}
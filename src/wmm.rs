@@ -0,0 +1,314 @@
+use std::path::Path;
+
+use chrono::{DateTime, Datelike, NaiveDate, Timelike};
+
+use crate::prelude::*;
+
+#[derive(thiserror::Error, Debug)]
+pub enum WmmError {
+    #[error("IO Error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Invalid WMM coefficient file at line {0}")]
+    Format(usize),
+    #[error("Date {0} is outside the model validity [{1}, {2}]")]
+    DateOutOfRange(f64, f64, f64),
+}
+
+/// Geomagnetic field at a point, components in the local geodetic frame
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MagneticField {
+    /// nT
+    pub north: f64,
+    /// nT
+    pub east: f64,
+    /// nT
+    pub down: f64,
+    /// angle from true north to magnetic north, positive east
+    pub declination: Radians,
+    /// angle below the horizontal
+    pub inclination: Radians,
+    /// total intensity (nT)
+    pub intensity: f64,
+    /// horizontal intensity (nT)
+    pub horizontal: f64,
+}
+
+impl MagneticField {
+    pub fn magnetic_to_true(&self, heading: impl Into<Radians>) -> Radians {
+        (heading.into() + self.declination).wrap_two_pi()
+    }
+
+    pub fn true_to_magnetic(&self, heading: impl Into<Radians>) -> Radians {
+        (heading.into() - self.declination).wrap_two_pi()
+    }
+}
+
+/// World Magnetic Model: a spherical-harmonic expansion with secular variation, loaded from the
+/// NOAA/BGS `WMM.COF` file. See The US/UK World Magnetic Model technical report, section 1.2.
+pub struct Wmm {
+    pub name: String,
+    /// decimal year
+    pub epoch: f64,
+    degree: usize,
+    /// main field (g, h) and secular variation (ġ, ḣ) indexed by [n][m], nT and nT/year
+    g: Vec<Vec<f64>>,
+    h: Vec<Vec<f64>>,
+    g_dot: Vec<Vec<f64>>,
+    h_dot: Vec<Vec<f64>>,
+}
+
+/// Model validity after its epoch (years)
+const VALIDITY_YEARS: f64 = 5.;
+
+/// Decimal year of a UTC timestamp (seconds since the Unix epoch)
+pub fn decimal_year(utc_secs: f64) -> f64 {
+    let date = DateTime::from_timestamp(utc_secs.floor() as i64, 0).unwrap_or_default();
+    let year = date.year();
+    let days_in_year = if NaiveDate::from_ymd_opt(year, 2, 29).is_some() { 366. } else { 365. };
+    let day = date.ordinal0() as f64 + (date.num_seconds_from_midnight() as f64 + utc_secs.fract()) / 86400.;
+    year as f64 + day / days_in_year
+}
+
+impl Wmm {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, WmmError> {
+        Self::from_cof(&std::fs::read_to_string(path)?)
+    }
+
+    pub fn from_cof(text: &str) -> Result<Self, WmmError> {
+        let mut lines = text.lines().enumerate().filter(|(_, l)| !l.trim().is_empty());
+        let (_, header) = lines.next().ok_or(WmmError::Format(1))?;
+        let mut header_fields = header.split_whitespace();
+        let epoch: f64 = header_fields.next().and_then(|e| e.parse().ok()).ok_or(WmmError::Format(1))?;
+        let name = header_fields.next().unwrap_or("WMM").to_owned();
+
+        let mut coefficients = Vec::new();
+        for (index, line) in lines {
+            //The file ends with lines of 9s
+            if line.trim_start().starts_with("9999") {
+                break;
+            }
+            let fields = line
+                .split_whitespace()
+                .map(|f| f.parse::<f64>().ok())
+                .collect::<Option<Vec<f64>>>()
+                .filter(|f| f.len() == 6)
+                .ok_or(WmmError::Format(index + 1))?;
+            let (n, m) = (fields[0] as usize, fields[1] as usize);
+            if n == 0 || m > n {
+                return Err(WmmError::Format(index + 1));
+            }
+            coefficients.push((n, m, fields[2], fields[3], fields[4], fields[5]));
+        }
+
+        let degree = coefficients.iter().map(|c| c.0).max().ok_or(WmmError::Format(2))?;
+        let table = || vec![vec![0.; degree + 1]; degree + 1];
+        let mut wmm = Self {
+            name,
+            epoch,
+            degree,
+            g: table(),
+            h: table(),
+            g_dot: table(),
+            h_dot: table(),
+        };
+        for (n, m, g, h, g_dot, h_dot) in coefficients {
+            wmm.g[n][m] = g;
+            wmm.h[n][m] = h;
+            wmm.g_dot[n][m] = g_dot;
+            wmm.h_dot[n][m] = h_dot;
+        }
+        Ok(wmm)
+    }
+
+    /// Field at `lla` and `utc_secs` (seconds since the Unix epoch)
+    pub fn field(&self, lla: &LLA, utc_secs: f64) -> Result<MagneticField, WmmError> {
+        self.field_at_year(lla, decimal_year(utc_secs))
+    }
+
+    pub fn field_at_year(&self, lla: &LLA, year: f64) -> Result<MagneticField, WmmError> {
+        if !(self.epoch..=self.epoch + VALIDITY_YEARS).contains(&year) {
+            return Err(WmmError::DateOutOfRange(year, self.epoch, self.epoch + VALIDITY_YEARS));
+        }
+        let dt = year - self.epoch;
+
+        //Geocentric spherical coordinates
+        let ecef = lla.to_ecef();
+        let p = (ecef.x * ecef.x + ecef.y * ecef.y).sqrt();
+        let r = (p * p + ecef.z * ecef.z).sqrt();
+        let geocentric_latitude = ecef.z.atan2(p);
+        let (mu, s) = geocentric_latitude.sin_cos();
+        //Y' divides by cos φ', keep it finite at the poles
+        let s = s.max(1e-10);
+
+        //Schmidt semi-normalised associated Legendre functions of cos θ = sin φ' and their θ derivatives
+        let size = self.degree + 1;
+        let mut legendre = vec![vec![0.; size]; size];
+        let mut derivative = vec![vec![0.; size]; size];
+        legendre[0][0] = 1.;
+        for n in 1..size {
+            for m in 0..=n {
+                if n == m {
+                    let k = if n == 1 { 1. } else { ((2 * n - 1) as f64 / (2 * n) as f64).sqrt() };
+                    legendre[n][n] = k * s * legendre[n - 1][n - 1];
+                    derivative[n][n] = k * (mu * legendre[n - 1][n - 1] + s * derivative[n - 1][n - 1]);
+                } else {
+                    let k1 = (2 * n - 1) as f64;
+                    let k2 = if n >= 2 { (((n - 1) * (n - 1) - m * m) as f64).sqrt() } else { 0. };
+                    let k3 = ((n * n - m * m) as f64).sqrt();
+                    let (previous2, previous2_derivative) = if n >= 2 { (legendre[n - 2][m], derivative[n - 2][m]) } else { (0., 0.) };
+                    legendre[n][m] = (k1 * mu * legendre[n - 1][m] - k2 * previous2) / k3;
+                    derivative[n][m] = (k1 * (mu * derivative[n - 1][m] - s * legendre[n - 1][m]) - k2 * previous2_derivative) / k3;
+                }
+            }
+        }
+
+        let (mut x, mut y, mut z) = (0., 0., 0.);
+        let ratio = Earth::GEOMAGNETIC_RADIUS / r;
+        let mut ratio_power = ratio * ratio;
+        for n in 1..size {
+            ratio_power *= ratio;
+            for m in 0..=n {
+                let g = self.g[n][m] + dt * self.g_dot[n][m];
+                let h = self.h[n][m] + dt * self.h_dot[n][m];
+                let (sin_m, cos_m) = (m as f64 * lla.lon().0).sin_cos();
+                let cosine_term = g * cos_m + h * sin_m;
+                x += ratio_power * cosine_term * derivative[n][m];
+                y += ratio_power * m as f64 * (g * sin_m - h * cos_m) * legendre[n][m];
+                z -= ratio_power * (n + 1) as f64 * cosine_term * legendre[n][m];
+            }
+        }
+        y /= s;
+
+        //Rotate from geocentric to geodetic
+        let (sin_delta, cos_delta) = (geocentric_latitude - lla.lat().0).sin_cos();
+        let north = x * cos_delta - z * sin_delta;
+        let down = x * sin_delta + z * cos_delta;
+        let east = y;
+        let horizontal = (north * north + east * east).sqrt();
+
+        Ok(MagneticField {
+            north,
+            east,
+            down,
+            declination: Radians(east.atan2(north)),
+            inclination: Radians(down.atan2(horizontal)),
+            intensity: (horizontal * horizontal + down * down).sqrt(),
+            horizontal,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lla::lla;
+
+    /// Axial dipole `g10` with secular variation `g10_dot`, plus a zero degree 2 term
+    fn dipole(g10: f64, g10_dot: f64) -> Wmm {
+        let cof = format!(
+            "    2020.0            DIPOLE        12/10/2019\n  1  0  {} 0.0 {} 0.0\n  1  1  0.0 0.0 0.0 0.0\n  2  0  0.0 0.0 0.0 0.0\n999999999999999999999999999999999999999999999999\n",
+            g10, g10_dot
+        );
+        Wmm::from_cof(&cof).unwrap()
+    }
+
+    /// Geocentric latitude and `(a / r)³` of a point
+    fn geocentric(lla: &LLA) -> (f64, f64) {
+        let ecef = lla.to_ecef();
+        let p = (ecef.x * ecef.x + ecef.y * ecef.y).sqrt();
+        let r = (p * p + ecef.z * ecef.z).sqrt();
+        (ecef.z.atan2(p), (Earth::GEOMAGNETIC_RADIUS / r).powi(3))
+    }
+
+    #[test]
+    fn axial_dipole() {
+        let g10 = -29404.8;
+        let wmm = dipole(g10, 0.);
+        for point in [lla(0., 30., 0.), lla(45., -100., 1000.), lla(-60., 170., 0.)] {
+            let field = wmm.field_at_year(&point, 2020.).unwrap();
+            //X = -g10 (a/r)³ cos φ' and Z = -2 g10 (a/r)³ sin φ' in the geocentric frame
+            let (latitude, ratio) = geocentric(&point);
+            let (x, z) = (-g10 * ratio * latitude.cos(), -2. * g10 * ratio * latitude.sin());
+            let delta = latitude - point.lat().0;
+            assert!((field.north - (x * delta.cos() - z * delta.sin())).abs() < 1e-6, "{:?}", field);
+            assert!((field.down - (x * delta.sin() + z * delta.cos())).abs() < 1e-6, "{:?}", field);
+            assert!(field.east.abs() < 1e-6 && field.declination.0.abs() < 1e-9, "{:?}", field);
+        }
+        //Field lines point down in the northern hemisphere
+        assert!(wmm.field_at_year(&lla(45., 0., 0.), 2020.).unwrap().inclination.0 > 0.);
+    }
+
+    #[test]
+    fn secular_variation() {
+        let wmm = dipole(-29404.8, 6.7);
+        let point = lla(0., 0., 0.);
+        let ratio = geocentric(&point).1;
+        let north = wmm.field_at_year(&point, 2022.5).unwrap().north;
+        assert!((north - (29404.8 - 2.5 * 6.7) * ratio).abs() < 1e-6, "{}", north);
+    }
+
+    #[test]
+    fn validity_window() {
+        let wmm = dipole(-29404.8, 0.);
+        assert!(matches!(wmm.field_at_year(&lla(0., 0., 0.), 2019.9), Err(WmmError::DateOutOfRange(..))));
+        assert!(matches!(wmm.field_at_year(&lla(0., 0., 0.), 2025.1), Err(WmmError::DateOutOfRange(..))));
+        assert!(wmm.field_at_year(&lla(0., 0., 0.), 2025.).is_ok());
+    }
+
+    #[test]
+    fn rejects_bad_coefficients() {
+        assert!(matches!(Wmm::from_cof(""), Err(WmmError::Format(1))));
+        assert!(matches!(Wmm::from_cof("2020.0 WMM\n  1  0  -29404.8 0.0 6.7\n"), Err(WmmError::Format(2))));
+        assert!(matches!(Wmm::from_cof("2020.0 WMM\n  1  2  0.0 0.0 0.0 0.0\n"), Err(WmmError::Format(2))));
+    }
+
+    #[test]
+    fn decimal_years() {
+        //2020-01-01T00:00:00Z and 2020-07-02T00:00:00Z, a leap year
+        assert_eq!(decimal_year(1577836800.), 2020.);
+        assert!((decimal_year(1593648000.) - (2020. + 183. / 366.)).abs() < 1e-12);
+        //2021-01-01T12:00:00Z
+        assert!((decimal_year(1609502400.) - (2021. + 0.5 / 365.)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn heading_conversions() {
+        let field = MagneticField { declination: Degrees(-10.).to_radians(), ..dipole(-29404.8, 0.).field_at_year(&lla(0., 0., 0.), 2020.).unwrap() };
+        assert!((field.magnetic_to_true(Degrees(5.)).to_degrees().0 - 355.).abs() < 1e-9);
+        assert!((field.true_to_magnetic(Degrees(355.)).to_degrees().0 - 5.).abs() < 1e-9);
+    }
+
+    /// NOAA test values shipped with the model (e.g. WMM2020_TEST_VALUES.txt), columns found from the `#` header line.
+    /// Needs `WMM.COF` and `WMM_TEST_VALUES.txt` in `data/` (see `data/README.md`) or at `WMM_COF` and `WMM_TEST_VALUES`
+    #[test]
+    #[ignore = "needs data/WMM.COF and data/WMM_TEST_VALUES.txt, which are not vendored yet"]
+    fn noaa_test_values() {
+        let data = |variable: &str, name: &str| std::env::var(variable).unwrap_or_else(|_| format!("{}/data/{}", env!("CARGO_MANIFEST_DIR"), name));
+        let wmm = Wmm::load(data("WMM_COF", "WMM.COF")).unwrap();
+        let text = std::fs::read_to_string(data("WMM_TEST_VALUES", "WMM_TEST_VALUES.txt")).unwrap();
+        let mut columns = Vec::new();
+        let mut checked = 0;
+        for line in text.lines().map(str::trim).filter(|l| !l.is_empty()) {
+            if let Some(header) = line.strip_prefix('#') {
+                if header.split_whitespace().any(|c| c == "Decl") {
+                    columns = header.split_whitespace().map(str::to_owned).collect();
+                }
+                continue;
+            }
+            let values: Vec<f64> = line.split_whitespace().map(|v| v.parse().unwrap()).collect();
+            let column = |names: &[&str]| {
+                let index = columns.iter().position(|c| names.contains(&c.as_str())).unwrap_or_else(|| panic!("no {:?} column in {:?}", names, columns));
+                values[index]
+            };
+            let point = lla(column(&["Lat"]), column(&["Lon", "Long"]), (column(&["HAE", "Height"]) * 1000.) as f32);
+            let field = wmm.field_at_year(&point, column(&["Date", "Year"])).unwrap();
+            let message = format!("{}: {:?}", line, field);
+            //Published to 0.1 nT and 0.01°
+            assert!((field.north - column(&["X"])).abs() < 0.1 && (field.east - column(&["Y"])).abs() < 0.1 && (field.down - column(&["Z"])).abs() < 0.1, "{}", message);
+            assert!((field.declination.to_degrees().0 - column(&["Decl"])).abs() < 0.01, "{}", message);
+            assert!((field.inclination.to_degrees().0 - column(&["Incl"])).abs() < 0.01, "{}", message);
+            checked += 1;
+        }
+        assert!(checked > 0, "no test values found");
+    }
+}